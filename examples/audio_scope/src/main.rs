@@ -2,7 +2,7 @@ use macroquad::prelude::*;
 
 use audioviz::audio_capture::capture::{Capture, Device};
 use audioviz::distributor::Distributor;
-use audioviz::utils::{seperate_channels, apodize};
+use audioviz::utils::{downmix_to_mono, apodize};
 
 use audioviz::processor::{Processor, Plugin, Bandpass};
 
@@ -34,12 +34,7 @@ async fn main() {
             distributor.push_auto(&data);
         }
         let data = distributor.pop_auto(None);
-        let data = seperate_channels(&data, audio_capture.channel_count.unwrap() as usize);
-        let mut data: Vec<f32> = if !data.is_empty() {
-            data[0].clone()
-        } else {
-            vec![]
-        };
+        let mut data = downmix_to_mono(&data, audio_capture.channel_count.unwrap() as usize);
         buffer.append(&mut data);
 
         let wanted_buf_size: u64 = BUFFER_LENGTH as u64; 
@@ -0,0 +1,32 @@
+use audioviz::audio_capture::capture::Device;
+use audioviz::spectrum::config::{StreamConfig, ProcessorConfig};
+use audioviz::pipeline::Pipeline;
+
+use std::{thread::sleep, time::Duration};
+
+fn main() {
+    let pipeline = Pipeline::builder()
+        .device(Device::DefaultInput)
+        .stream_config(StreamConfig {
+            processor: ProcessorConfig {
+                resolution: Some(64),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+
+    loop {
+        let frequencies = pipeline.get_frequencies();
+
+        // prints loudest frequency of every channel
+        for (channel, freqs) in frequencies.iter().enumerate() {
+            if let Some(loudest) = freqs.iter().max_by(|a, b| a.volume.partial_cmp(&b.volume).unwrap()) {
+                println!("channel {}: {:.0}hz at {:.2}", channel, loudest.freq, loudest.volume);
+            }
+        }
+
+        sleep(Duration::from_millis(100));
+    }
+}
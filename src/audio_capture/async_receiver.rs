@@ -0,0 +1,100 @@
+//! ## Example
+//! ```no_run
+//! use audioviz::audio_capture::capture::{Capture, Device};
+//!
+//! let mut capture = Capture::new();
+//! capture.init(&Device::DefaultInput).unwrap();
+//! let mut samples = capture.get_receiver().unwrap().into_stream();
+//!
+//! async {
+//!     while let Some(data) = samples.next().await {
+//!         // process data
+//!     }
+//! };
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use super::capture::CaptureReceiver;
+
+// maximum number of chunks that are queued for the stream,
+// when it is full captured data is collected by the capture and delivered in one bigger chunk instead
+const QUEUE_CAPACITY: usize = 16;
+
+/// asynchronous stream of captured audio data, created with `CaptureReceiver::into_stream()`
+///
+/// a seperate thread blocks on the receiver and wakes the task when data arrives,
+/// so it does not depend on any specific async runtime.
+/// at most 16 chunks are queued, the thread stops at the next chunk after stream got dropped.
+///
+/// `poll_next()` has the same signature as `futures::Stream::poll_next()`, so it can easily be wrapped into one
+pub struct ReceiverStream {
+    receiver: Receiver<Vec<f32>>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+impl ReceiverStream {
+    /// stream never ends on its own, receiver keeps capture events alive as long as it exists
+    pub fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<f32>>> {
+        match self.receiver.try_recv() {
+            Ok(data) => return Poll::Ready(Some(data)),
+            Err(TryRecvError::Disconnected) => return Poll::Ready(None),
+            Err(TryRecvError::Empty) => (),
+        }
+        if let Ok(mut waker) = self.waker.lock() {
+            *waker = Some(cx.waker().clone());
+        }
+
+        // data could have arrived before waker was registered
+        match self.receiver.try_recv() {
+            Ok(data) => Poll::Ready(Some(data)),
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+            Err(TryRecvError::Empty) => Poll::Pending,
+        }
+    }
+
+    /// waits for next chunk of audio data, like `futures::StreamExt::next()`
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Next<'_> {
+        Next { stream: self }
+    }
+}
+
+/// future returned by `ReceiverStream::next()`
+pub struct Next<'a> {
+    stream: &'a mut ReceiverStream,
+}
+impl Future for Next<'_> {
+    type Output = Option<Vec<f32>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.stream).poll_next(cx)
+    }
+}
+
+impl CaptureReceiver {
+    /// converts receiver into an asynchronous stream of audio data
+    pub fn into_stream(self) -> ReceiverStream {
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let waker: Arc<Mutex<Option<Waker>>> = Arc::new(Mutex::new(None));
+
+        let thread_waker = waker.clone();
+        thread::spawn(move || {
+            while let Some(data) = self.wait_data() {
+                // blocks while queue is full, fails after stream got dropped
+                if sender.send(data).is_err() {
+                    return;
+                }
+                if let Some(waker) = thread_waker.lock().ok().and_then(|mut w| w.take()) {
+                    waker.wake();
+                }
+            }
+        });
+
+        ReceiverStream { receiver, waker }
+    }
+}
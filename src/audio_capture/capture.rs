@@ -9,7 +9,7 @@
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use log::warn;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, atomic::{AtomicBool, AtomicU32, Ordering}};
 use std::thread;
 
 use super::converter;
@@ -26,21 +26,50 @@ pub enum Error {
 enum CaptureEvent {
     SendData(Vec<f32>),
     ReceiveData(mpsc::Sender<Option<Vec<f32>>>),
+    // like `ReceiveData`, but answered once data is available
+    #[cfg(feature = "async")]
+    WaitData(mpsc::Sender<Option<Vec<f32>>>),
+    Clear,
 }
 
+/// device that gets captured
+///
+/// output devices capture what is currently played back on them (loopback),
+/// this is only supported by WASAPI on windows, other hosts fail to build a stream
+/// with `Error::UnsupportedConfig` or `Error::BackendSpecific`.
+/// on linux a monitor source of pulseaudio / pipewire can be captured as input device instead
 #[derive(Clone, Debug)]
 pub enum Device {
     DefaultInput,
     DefaultOutput,
+    /// index into input devices of host
     Id(usize),
+    /// index into output devices of host
+    OutputId(usize),
+    /// name of device as returned by `Capture::fetch_devices()`
+    ///
+    /// has to match exactly and is case-sensitive, devices with the same name can not be told apart.
+    /// output devices are only captured in loopback mode if they do not also support input
+    Name(String),
+}
+impl Device {
+    /// true for variants that always refer to output devices, which get captured in loopback mode
+    pub fn is_output(&self) -> bool {
+        matches!(self, Device::DefaultOutput | Device::OutputId(_))
+    }
 }
 
 pub struct CaptureReceiver {
     sender: mpsc::Sender<CaptureEvent>,
+    paused: Arc<AtomicBool>,
 }
 impl CaptureReceiver {
+    /// data captured since last call, `None` if there is none or capture is paused
     #[allow(unused_must_use)]
     pub fn receive_data(&self) -> Option<Vec<f32>> {
+        if self.paused.load(Ordering::Relaxed) {
+            return None;
+        }
         let (sender, receiver) = mpsc::channel();
         self.sender.send(CaptureEvent::ReceiveData(sender));
         match receiver.recv() {
@@ -48,15 +77,38 @@ impl CaptureReceiver {
             Err(_) => None,
         }
     }
+
+    // blocks until data was captured, `None` only if capture events were dropped
+    #[cfg(feature = "async")]
+    #[allow(unused_must_use)]
+    pub(crate) fn wait_data(&self) -> Option<Vec<f32>> {
+        let (sender, receiver) = mpsc::channel();
+        self.sender.send(CaptureEvent::WaitData(sender));
+        receiver.recv().ok().flatten()
+    }
 }
 
 pub struct Capture {
+    /// channel count of captured device, set by `init()`
     pub channel_count: Option<u16>,
+
+    /// sampling rate of captured device, set by `init()`
+    ///
+    /// should be used to configure `Distributor` and `ProcessorConfig.sampling_rate`
     pub sampling_rate: Option<u32>,
+
+    /// averages all channels into one before data gets sent to the receivers, must be set before `init()`
+    ///
+    /// `channel_count` will be reported as 1, stereo information is discarded
+    pub force_mono: bool,
     host: cpal::platform::Host,
+    // bits of f32 target rms, 0.0 when disabled
+    target_rms: Arc<AtomicU32>,
+    paused: Arc<AtomicBool>,
     // will receive data in constant intervall from distributor
     sender: Option<mpsc::Sender<CaptureEvent>>,
     stream: Option<cpal::Stream>,
+    device: Option<cpal::Device>,
 }
 impl Capture {
     pub fn new() -> Self{
@@ -65,39 +117,194 @@ impl Capture {
         return Self {
             sampling_rate: None,
             channel_count: None,
+            force_mono: false,
             host,
+            target_rms: Arc::new(AtomicU32::new(0.0_f32.to_bits())),
+            paused: Arc::new(AtomicBool::new(false)),
             sender: None,
             stream: None,
+            device: None,
         }
     }
+    /// starts capturing `device`
+    ///
+    /// after `stop()` the existing event handler is reused, so already requested receivers keep working
     pub fn init(&mut self, device: &Device) -> Result<(), Error> {
-        let (sender, receiver) = mpsc::channel();
+        if self.sender.is_some() {
+            return self.switch_device(device);
+        }
+        let result = self.start(device);
+        if result.is_err() {
+            // event handler exits once its last sender is dropped
+            self.sender = None;
+        }
+        result
+    }
 
-        let (channel_count, stream, sampling_rate) = match stream_audio_to_distributor(&self.host, sender.clone(), device) {
-            Ok(s) => s,
-            Err(e) => return Err(e),
-        };
+    /// replaces the captured device, already requested receivers keep working
+    ///
+    /// old stream gets stopped before the new one is built,
+    /// `channel_count` and `sampling_rate` get updated because they may differ between devices
+    ///
+    /// acts like `init()` if capture is not yet initialized.
+    /// on error old stream is already stopped, so `sampling_rate` and `channel_count` are `None`
+    /// until a device is switched to successfully
+    pub fn switch_device(&mut self, device: &Device) -> Result<(), Error> {
+        if self.sender.is_none() {
+            return self.init(device);
+        }
+        self.release();
+        self.start(device)
+    }
 
-        // initiates event handler
-        thread::spawn(move || {
-            handle_events(receiver);
-        });
+    // builds stream of `device` that feeds the event handler
+    fn start(&mut self, device: &Device) -> Result<(), Error> {
+        let (device, config) = open_device(&self.host, device)?;
+        let callback = self.attach(config.channels(), config.sample_rate().0)?;
+        match build_stream(&device, config, callback) {
+            Ok(stream) => {
+                self.stream = Some(stream);
+                self.device = Some(device);
+                Ok(())
+            }
+            Err(e) => {
+                self.release();
+                Err(e)
+            }
+        }
+    }
+
+    // starts event handler if there is none yet and reports config of a device with `channel_count` channels
+    //
+    // returned callback sends data of the device to the event handler
+    fn attach(&mut self, channel_count: u16, sampling_rate: u32) -> Result<CaptureCallback, Error> {
+        validate_config(channel_count, sampling_rate)?;
+        let sender = match &self.sender {
+            Some(sender) => sender.clone(),
+            None => {
+                let (sender, receiver) = mpsc::channel();
+                // initiates event handler
+                thread::spawn(move || {
+                    handle_events(receiver);
+                });
+                self.sender = Some(sender.clone());
+                sender
+            }
+        };
 
+        self.paused.store(false, Ordering::Relaxed);
         self.sampling_rate = Some(sampling_rate);
-        self.channel_count = Some(channel_count);
-        self.stream = Some(stream);
-        self.sender = Some(sender);
+        self.channel_count = Some(if self.force_mono { 1 } else { channel_count });
+
+        // channels that get averaged in callback, 1 means data is passed through
+        let downmix: usize = if self.force_mono { channel_count as usize } else { 1 };
+        Ok(CaptureCallback {
+            sender,
+            downmix,
+            target_rms: self.target_rms.clone(),
+            paused: self.paused.clone(),
+            normalizer: converter::Normalizer::new(0.0),
+        })
+    }
+
+    // old stream stops capturing when dropped
+    fn release(&mut self) {
+        self.stream = None;
+        self.device = None;
+        self.sampling_rate = None;
+        self.channel_count = None;
+    }
+
+    // same as `switch_device()` but to a device without stream, data is pushed through the returned callback
+    #[cfg(test)]
+    pub(crate) fn switch_to_synthetic(&mut self, channel_count: u16, sampling_rate: u32) -> Result<CaptureCallback, Error> {
+        self.release();
+        self.attach(channel_count, sampling_rate)
+    }
+
+    /// temporarily stops capturing, for example while a window is minimized
+    ///
+    /// data that was not yet received gets discarded and receivers return `None` until `resume()`.
+    /// if the stream can not be paused the error is returned and capturing continues.
+    /// does nothing if capture is not initialized
+    #[allow(unused_must_use)]
+    pub fn pause(&mut self) -> Result<(), Error> {
+        if self.channel_count.is_none() {
+            return Ok(());
+        }
+        // synthetic devices of tests have no stream
+        if let Some(stream) = &self.stream {
+            stream.pause().map_err(|e| match e {
+                cpal::PauseStreamError::DeviceNotAvailable => Error::DeviceNotAvailable,
+                cpal::PauseStreamError::BackendSpecific { err } => Error::BackendSpecific(err.to_string()),
+            })?;
+        }
+        self.paused.store(true, Ordering::Relaxed);
+        if let Some(sender) = &self.sender {
+            sender.send(CaptureEvent::Clear);
+        }
+        Ok(())
+    }
 
+    /// continues capturing after `pause()`, does nothing if capture is not initialized
+    pub fn resume(&mut self) -> Result<(), Error> {
+        if self.channel_count.is_none() {
+            return Ok(());
+        }
+        if let Some(stream) = &self.stream {
+            stream.play().map_err(|e| match e {
+                cpal::PlayStreamError::DeviceNotAvailable => Error::DeviceNotAvailable,
+                cpal::PlayStreamError::BackendSpecific { err } => Error::BackendSpecific(err.to_string()),
+            })?;
+        }
+        self.paused.store(false, Ordering::Relaxed);
         Ok(())
     }
 
+    /// stops capturing and releases the device, `sampling_rate` and `channel_count` become `None`
+    ///
+    /// event handler is kept alive, so that a later `init()` reattaches to already requested receivers
+    #[allow(unused_must_use)]
+    pub fn stop(&mut self) {
+        self.release();
+        self.paused.store(false, Ordering::Relaxed);
+        if let Some(sender) = &self.sender {
+            sender.send(CaptureEvent::Clear);
+        }
+    }
+
+    /// normalizes captured audio so that its RMS slowly approaches `target`
+    ///
+    /// acts like a slow automatic gain control on the input, so that levels are
+    /// consistent regardless of device gain. gain is smoothed over time to avoid pumping.
+    ///
+    /// can be called before and after `init()`, `target <= 0.0` disables normalisation
+    pub fn set_target_rms(&mut self, target: f32) {
+        self.target_rms.store(target.max(0.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// underlying cpal stream, `None` if capture is not initialized
+    ///
+    /// for cpal specific functionality that is not covered by `Capture`,
+    /// pausing or otherwise modifying the stream directly can break assumptions of `Capture` and its receivers
+    pub fn cpal_stream(&self) -> Option<&cpal::Stream> {
+        self.stream.as_ref()
+    }
+
+    /// underlying cpal device that is currently captured, `None` if capture is not initialized
+    ///
+    /// same caveats as `cpal_stream()` apply
+    pub fn cpal_device(&self) -> Option<&cpal::Device> {
+        self.device.as_ref()
+    }
+
     /// request a receiver that receives the distributed audio data as f32 samples
     ///
     /// you can request multiple receivers out of one Capture
     #[allow(unused_must_use)]
     pub fn get_receiver(&self) -> Option<CaptureReceiver> {
         if let Some(sender) = self.sender.clone() {
-            Some(CaptureReceiver {sender})
+            Some(CaptureReceiver {sender, paused: self.paused.clone()})
         } else {
             None
         }
@@ -125,82 +332,156 @@ impl Capture {
 #[allow(unused_must_use)]
 fn handle_events(receiver: mpsc::Receiver<CaptureEvent>) {
     let mut data: Vec<f32> = Vec::new();
+    let mut waiting: Vec<mpsc::Sender<Option<Vec<f32>>>> = Vec::new();
 
-    loop {
-        if let Ok(event) = receiver.recv() {
-            match event {
-                CaptureEvent::SendData(mut d) => {
-                    data.append(&mut d);
-                }
-                CaptureEvent::ReceiveData(sender) => {
-                    //sender.send(data.clone());
-                    if !data.is_empty() {
-                        sender.send(Some(data.clone()));
-                    } else {
-                        sender.send(None);
+    while let Ok(event) = receiver.recv() {
+        match event {
+            CaptureEvent::SendData(mut d) => {
+                data.append(&mut d);
+                // waiters whose receiver is gone are skipped, data is kept for the next one
+                while !data.is_empty() && !waiting.is_empty() {
+                    match waiting.remove(0).send(Some(std::mem::take(&mut data))) {
+                        Ok(()) => break,
+                        Err(mpsc::SendError(unsent)) => data = unsent.unwrap_or_default(),
                     }
-                    data.drain(..);
                 }
             }
+            CaptureEvent::ReceiveData(sender) => {
+                //sender.send(data.clone());
+                if !data.is_empty() {
+                    sender.send(Some(data.clone()));
+                } else {
+                    sender.send(None);
+                }
+                data.drain(..);
+            }
+            #[cfg(feature = "async")]
+            CaptureEvent::WaitData(sender) => {
+                if data.is_empty() {
+                    waiting.push(sender);
+                } else if let Err(mpsc::SendError(unsent)) = sender.send(Some(std::mem::take(&mut data))) {
+                    data = unsent.unwrap_or_default();
+                }
+            }
+            CaptureEvent::Clear => data.clear(),
         }
     }
 }
 
-fn stream_audio_to_distributor(
-    host: &cpal::platform::Host,
+// state of cpal data callback
+pub(crate) struct CaptureCallback {
     sender: mpsc::Sender<CaptureEvent>,
-    device: &Device,
-    // returns channel-count, stream and sampling-rate
-) -> Result<(u16, cpal::Stream, u32), Error> {
-    let device = match device {
-        &Device::DefaultInput => match host.default_input_device() {
+    downmix: usize,
+    target_rms: Arc<AtomicU32>,
+    paused: Arc<AtomicBool>,
+    normalizer: converter::Normalizer,
+}
+impl CaptureCallback {
+    #[allow(unused_must_use)]
+    pub(crate) fn send(&mut self, data: &[f32]) {
+        if self.paused.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut data = converter::to_mono(data, self.downmix);
+
+        let target = f32::from_bits(self.target_rms.load(Ordering::Relaxed));
+        if target > 0.0 {
+            self.normalizer.target = target;
+            self.normalizer.process(&mut data);
+        }
+
+        self.sender.send(CaptureEvent::SendData(data));
+    }
+}
+
+// lowest and highest sampling rate that is considered plausible
+const MIN_SAMPLING_RATE: u32 = 1_000;
+const MAX_SAMPLING_RATE: u32 = 1_536_000;
+
+// some virtual devices report configs that would cause division by zero later on
+pub(crate) fn validate_config(channel_count: u16, sampling_rate: u32) -> Result<(), Error> {
+    if channel_count == 0 {
+        warn!("device reported config with 0 channels");
+        return Err(Error::UnsupportedConfig);
+    }
+    if !(MIN_SAMPLING_RATE..=MAX_SAMPLING_RATE).contains(&sampling_rate) {
+        warn!(
+            "device reported implausible sampling rate of {}hz, expected {}hz to {}hz",
+            sampling_rate, MIN_SAMPLING_RATE, MAX_SAMPLING_RATE
+        );
+        return Err(Error::UnsupportedConfig);
+    }
+    Ok(())
+}
+
+// device and its config that gets captured, output devices are opened in loopback mode
+fn open_device(host: &cpal::platform::Host, device: &Device) -> Result<(cpal::Device, cpal::SupportedStreamConfig), Error> {
+    let device_kind = device;
+    let device = match *device {
+        Device::DefaultInput => match host.default_input_device() {
             Some(d) => d,
             None => return Err(Error::DeviceNotFound),
         },
-        &Device::DefaultOutput => match host.default_output_device() {
+        Device::DefaultOutput => match host.default_output_device() {
             Some(d) => d,
             None => return Err(Error::DeviceNotFound),
         },
-        &Device::Id(id) => match host.input_devices() {
+        Device::Id(id) => match host.input_devices() {
+            Ok(mut devices) => match devices.nth(id) {
+                Some(d) => d,
+                None => return Err(Error::DeviceNotFound),
+            },
+            Err(_) => return Err(Error::DeviceNotFound),
+        },
+        Device::OutputId(id) => match host.output_devices() {
             Ok(mut devices) => match devices.nth(id) {
                 Some(d) => d,
                 None => return Err(Error::DeviceNotFound),
             },
             Err(_) => return Err(Error::DeviceNotFound),
         },
+        Device::Name(ref name) => match host.devices() {
+            Ok(mut devices) => match devices.find(|d| d.name().map(|n| n == *name).unwrap_or(false)) {
+                Some(d) => d,
+                None => return Err(Error::DeviceNotFound),
+            },
+            Err(_) => return Err(Error::DeviceNotFound),
+        },
     };
 
-    let config: cpal::SupportedStreamConfig = match device.default_input_config() {
-        Ok(c) => c,
-        Err(_) => return Err(Error::DeviceNotAvailable),
+    let loopback = match device_kind {
+        Device::Name(_) => device.default_input_config().is_err(),
+        kind => kind.is_output(),
     };
 
-    let channel_count = config.channels();
-    let sampling_rate = config.sample_rate();
+    // on WASAPI building an input stream with the config of an output device enables loopback
+    let config = if loopback {
+        device.default_output_config()
+    } else {
+        device.default_input_config()
+    };
+    match config {
+        Ok(config) => Ok((device, config)),
+        Err(_) => Err(Error::DeviceNotAvailable),
+    }
+}
 
-    #[allow(unused_must_use)]
+// starts capturing `device` with `config`, captured data is passed to `callback`
+fn build_stream(device: &cpal::Device, config: cpal::SupportedStreamConfig, mut callback: CaptureCallback) -> Result<cpal::Stream, Error> {
     let stream = match config.sample_format() {
         cpal::SampleFormat::F32 => device.build_input_stream(
             &config.into(),
-            move |data: &[f32], _: &_| {
-                sender.send(CaptureEvent::SendData(data.to_vec()));
-            },
+            move |data: &[f32], _: &_| callback.send(data),
             |e| warn!("error occurred on capture-stream: {}", e),
         ),
         cpal::SampleFormat::I16 => device.build_input_stream(
             &config.into(),
-            move |data: &[i16], _: &_| {
-                let data = converter::i16_to_f32(data);
-                sender.send(CaptureEvent::SendData(data.to_vec()));
-            },
+            move |data: &[i16], _: &_| callback.send(&converter::i16_to_f32(data)),
             |e| warn!("error occurred on capture-stream: {}", e),
         ),
         cpal::SampleFormat::U16 => device.build_input_stream(
             &config.into(),
-            move |data: &[u16], _: &_| {
-                let data = converter::u16_to_f32(data);
-                sender.send(CaptureEvent::SendData(data.to_vec()));
-            },
+            move |data: &[u16], _: &_| callback.send(&converter::u16_to_f32(data)),
             |e| warn!("error occurred on capture-stream: {}", e),
         ),
     };
@@ -221,5 +502,5 @@ fn stream_audio_to_distributor(
 
     stream.play().unwrap();
 
-    Ok((channel_count, stream, sampling_rate.0))
+    Ok(stream)
 }
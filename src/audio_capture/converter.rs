@@ -14,4 +14,57 @@ pub fn u16_to_f32(sample: &[u16]) -> Vec<f32> {
         .collect();
 
     f32_sample
-}
\ No newline at end of file
+}
+
+/// averages interleaved samples of all channels into a single channel, same as `utils::downmix_to_mono()`
+pub fn to_mono(sample: &[f32], channel_count: usize) -> Vec<f32> {
+    crate::utils::downmix_to_mono(sample, channel_count)
+}
+
+/// slowly adjusts gain of incoming samples so that their RMS approaches `target`
+///
+/// gain only moves a small step towards the wanted gain on every processed block,
+/// so that short quiet or loud passages do not cause audible pumping
+#[derive(Copy, Clone, Debug)]
+pub struct Normalizer {
+    pub target: f32,
+
+    /// fraction of the difference to wanted gain that gets applied per block (0..=1)
+    pub smoothing: f32,
+
+    /// upper limit of gain, prevents silence from being amplified to noise
+    pub max_gain: f32,
+
+    gain: f32,
+}
+impl Normalizer {
+    pub fn new(target: f32) -> Self {
+        Self {
+            target,
+            smoothing: 0.05,
+            max_gain: 100.0,
+            gain: 1.0,
+        }
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    pub fn process(&mut self, sample: &mut [f32]) {
+        if sample.is_empty() {
+            return;
+        }
+        let rms = crate::utils::rms(sample);
+
+        // gain is kept during silence
+        if rms > 1e-6 {
+            let wanted = (self.target / rms).min(self.max_gain);
+            self.gain += (wanted - self.gain) * self.smoothing;
+        }
+
+        for x in sample.iter_mut() {
+            *x *= self.gain;
+        }
+    }
+}
@@ -2,3 +2,6 @@
 
 pub mod capture;
 pub mod converter;
+
+#[cfg(feature = "async")]
+pub mod async_receiver;
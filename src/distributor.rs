@@ -63,8 +63,10 @@
 //! 
 //! If the `std` feature is enabled, time measurement can be done by the Distributor itself
 //! using `pop_auto()` and `push_auto()`
+use std::collections::VecDeque;
+
 #[cfg(feature = "std")]
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 #[derive(Clone, Debug)]
 pub struct Distributor<T> {
@@ -74,14 +76,47 @@ pub struct Distributor<T> {
     /// in data bits per second, (Hz)
     pub data_rate: f64,
 
+    /// maximum length of `buffer`, enforced on every push by dropping oldest data, `None` or 0 for unbounded
+    ///
+    /// dropped amount gets rounded up to a multiple of granularity, so that interleaved frames are not split
     pub max_buffer_length: Option<usize>,
 
+    /// total number of items that got dropped because `buffer` would have exceeded `max_buffer_length`
+    ///
+    /// increases when `pop()` is called too rarely
+    pub dropped: u64,
+
     fully_initialized: bool,
 
     // neccessarry for even better distribution
     send_amount_excess: f64,
     pub buffer: Vec<T>,
 
+    /// wanted length of `buffer`
+    ///
+    /// when set, the amount of data given away on each `pop()` gets biased
+    /// so that `buffer` stays around this length,
+    /// this compensates slow drift between the clocks of producer and consumer
+    /// and avoids dropping of data because of `max_buffer_length`
+    pub target_backlog: Option<usize>,
+
+    /// maximum fraction (0..=1) of `buffer` that can be given away on a single `pop()`
+    ///
+    /// keeps output continuous even if `data_rate` is overestimated,
+    /// instead of giving away the whole buffer at once.
+    /// data that is held back stays in buffer, which increases latency
+    pub max_pop_fraction: Option<f64>,
+
+    // length of every pop is a multiple of it, see `set_granularity()`
+    granularity: usize,
+
+    // long term average of `data_rate`, used when `target_backlog` is set
+    average_data_rate: f64,
+    // integral part of backlog controller
+    backlog_integral: f64,
+    // most recent `data_rate` estimates, used for `rate_stability()`
+    rate_history: VecDeque<f64>,
+
     #[cfg(feature = "std")]
     push_elapsed: Instant,
 
@@ -94,6 +129,29 @@ pub enum Elapsed {
     Micros(u128),
     Millis(u64)
 }
+#[cfg(feature = "std")]
+impl Elapsed {
+    /// same as `Elapsed::from(duration)`, uses nanoseconds to keep full precision
+    pub fn from_duration(duration: Duration) -> Self {
+        Elapsed::Nanos(duration.as_nanos())
+    }
+}
+#[cfg(feature = "std")]
+impl From<Duration> for Elapsed {
+    fn from(duration: Duration) -> Self {
+        Self::from_duration(duration)
+    }
+}
+
+// how fast `average_data_rate` follows `data_rate`
+const AVERAGE_SMOOTHING: f64 = 0.05;
+
+// proportional and integral gain of backlog controller
+const BACKLOG_P: f64 = 1.0;
+const BACKLOG_I: f64 = 0.1;
+
+// number of `data_rate` estimates considered by `rate_stability()`
+const RATE_HISTORY_LENGTH: usize = 16;
 
 impl<T: Clone> Distributor<T> {
     pub fn new(estimated_data_rate: f64, max_buffer_length: Option<usize>) -> Self {
@@ -103,10 +161,18 @@ impl<T: Clone> Distributor<T> {
             last_pop_size: 0,
             data_rate: estimated_data_rate,
             max_buffer_length,
+            dropped: 0,
 
             fully_initialized: false,
             send_amount_excess: 0.0,
             buffer: Vec::new(),
+
+            target_backlog: None,
+            max_pop_fraction: None,
+            granularity: 1,
+            average_data_rate: estimated_data_rate,
+            backlog_integral: 0.0,
+            rate_history: VecDeque::with_capacity(RATE_HISTORY_LENGTH),
         };
 
         #[cfg(feature = "std")]
@@ -120,6 +186,14 @@ impl<T: Clone> Distributor<T> {
             buffer: Vec::new(),
 
             max_buffer_length,
+            dropped: 0,
+
+            target_backlog: None,
+            max_pop_fraction: None,
+            granularity: 1,
+            average_data_rate: estimated_data_rate,
+            backlog_integral: 0.0,
+            rate_history: VecDeque::with_capacity(RATE_HISTORY_LENGTH),
 
             push_elapsed: Instant::now(),
             pop_elapsed: Instant::now(),
@@ -130,10 +204,28 @@ impl<T: Clone> Distributor<T> {
         self.buffer.clone()
     }
 
+    /// amount of data that is currently buffered, without cloning it
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
     pub fn clear(&mut self) {
         self.buffer.clear();
     }
 
+    /// rounds length of every pop down to a multiple of `n`
+    ///
+    /// setting it to the channel count keeps interleaved frames together, so that a frame never gets split between two pops.
+    /// rounded off data stays in buffer and gets carried over to the next pop,
+    /// this adds up to `n - 1` samples of latency
+    pub fn set_granularity(&mut self, n: usize) {
+        self.granularity = n.max(1);
+    }
+
     pub fn push(&mut self, buffer: &[T], elapsed: Elapsed) {
         self.last_buffer_size = buffer.len();
 
@@ -142,11 +234,13 @@ impl<T: Clone> Distributor<T> {
                 Elapsed::Nanos(elapsed) => (buffer.len() - self.last_pop_size) as f64 / elapsed as f64 * 1_000_000_000.0,
                 Elapsed::Micros(elapsed) => (buffer.len() - self.last_pop_size) as f64 / elapsed as f64 * 1_000_000.0,
                 Elapsed::Millis(elapsed) => (buffer.len() - self.last_pop_size) as f64 / elapsed as f64 * 1_000.0,
-            } 
+            };
+            self.record_data_rate();
         }
 
         self.buffer.append(&mut buffer.to_vec());
         self.fully_initialized = true;
+        self.enforce_max_buffer_length();
     }
 
     #[cfg(feature = "std")]
@@ -159,83 +253,133 @@ impl<T: Clone> Distributor<T> {
 
         if self.fully_initialized {
             self.data_rate = (buffer.len() - self.last_pop_size) as f64 / elapsed as f64 * 1_000_000.0;
+            self.record_data_rate();
         }
 
         self.buffer.append(&mut buffer.to_vec());
         self.fully_initialized = true;
+        self.enforce_max_buffer_length();
     }
+
+    // drops oldest data that exceeds `max_buffer_length`
+    fn enforce_max_buffer_length(&mut self) {
+        let cap: usize = match self.max_buffer_length {
+            Some(cap) if cap != 0 => cap,
+            _ => return,
+        };
+        if self.buffer.len() <= cap {
+            return;
+        }
+        let mut oversize: usize = self.buffer.len() - cap;
+        oversize += (self.granularity - oversize % self.granularity) % self.granularity;
+        let oversize: usize = oversize.min(self.buffer.len());
+
+        self.buffer.drain(0..oversize);
+        self.dropped += oversize as u64;
+    }
+
+    /// how stable `data_rate` was over the last 16 pushes in range (0..=1)
+    ///
+    /// derived from the coefficient of variation `cv` (standard deviation / mean)
+    /// of the recent estimates as `1 / (1 + cv)`, so steady push intervals result in values close to 1.0
+    ///
+    /// is 0.0 until at least two estimates are known
+    pub fn rate_stability(&self) -> f64 {
+        let len = self.rate_history.len();
+        if len < 2 {
+            return 0.0;
+        }
+        let mean: f64 = self.rate_history.iter().sum::<f64>() / len as f64;
+        if mean <= 0.0 || !mean.is_finite() {
+            return 0.0;
+        }
+        let variance: f64 = self.rate_history.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / len as f64;
+
+        1.0 / (1.0 + variance.sqrt() / mean)
+    }
+
+    // called after each new `data_rate` estimate
+    fn record_data_rate(&mut self) {
+        self.average_data_rate += (self.data_rate - self.average_data_rate) * AVERAGE_SMOOTHING;
+
+        if self.rate_history.len() >= RATE_HISTORY_LENGTH {
+            self.rate_history.pop_front();
+        }
+        self.rate_history.push_back(self.data_rate);
+    }
+
     /// array length is unknown and dependent data_rate and the interval between `pop()` calls
     /// 
     /// use `None` on `manual_data_rate` to use calculated data rate
     pub fn pop(&mut self, elapsed: Elapsed, manual_data_rate: Option<f64>) -> Vec<T> {
-        // calculates what amount to send for continous stream
-        //let send_amount: usize = ( (elapsed as f64 / 1_000_000.0 /* to convert from µs to s */) * self.data_rate ).round() as usize;
-        let mut send_amount: f64 = match elapsed {
+        let seconds: f64 = match elapsed {
             Elapsed::Nanos(elapsed) => elapsed as f64 / 1_000_000_000.0, /* to convert from ns to s */
             Elapsed::Micros(elapsed) => elapsed as f64 / 1_000_000.0,
             Elapsed::Millis(elapsed) => elapsed as f64 / 1_000.0,
         };
-        if let Some(data_rate) = manual_data_rate {
-            send_amount *= data_rate
-        } else {
-            send_amount *= self.data_rate
-        };
-
-        self.send_amount_excess += send_amount % 1.0;
-        let mut send_amount = send_amount.floor() as usize;
-
-        // handle of send_amount_excess
-        if self.send_amount_excess >= 1.0 {
-            send_amount += 1;
-            self.send_amount_excess -= 1.0;
-        }
-
-        let o_buffer: Vec<T>;
-        if self.buffer.len() > send_amount {
-            o_buffer = self.buffer[0..send_amount].to_vec();
-            self.buffer.drain(0..send_amount);
-        } else {
-            o_buffer = self.buffer.clone();
-            self.buffer.drain(..);
-        }
-
-        // prevents buffer to grow indefinetly, can happeen when
-        // distributor runs for hours
-        if let Some(max_buffer_length) = self.max_buffer_length {
-            let cap: usize = max_buffer_length;
-            if self.buffer.len() > cap && cap != 0 {
-                log::warn!("force reset of distribution buffer");
-                if self.buffer.len() > send_amount {
-                    let oversize: usize = self.buffer.len() - send_amount;
-                    self.buffer.drain(0..oversize);
-                }
-            }
-        }
 
-        o_buffer
+        self.distribute(seconds, manual_data_rate)
     }
 
     #[cfg(feature = "std")]
     /// same as `pop()` but with automatic time measurement
     pub fn pop_auto(&mut self, manual_data_rate: Option<f64>) -> Vec<T> {
-        // calculates what amount to send for continous stream
         let elapsed = self.pop_elapsed.elapsed().as_micros();
         self.pop_elapsed = Instant::now();
 
-        let mut send_amount: f64 = elapsed as f64 / 1_000_000.0;
+        self.distribute(elapsed as f64 / 1_000_000.0, manual_data_rate)
+    }
+
+    // gives away the amount of data that accumulated in `seconds`
+    fn distribute(&mut self, seconds: f64, manual_data_rate: Option<f64>) -> Vec<T> {
+        // calculates what amount to send for continous stream
+        let mut send_amount: f64 = seconds;
         if let Some(data_rate) = manual_data_rate {
             send_amount *= data_rate
+        } else if self.target_backlog.is_some() {
+            send_amount *= self.average_data_rate
         } else {
             send_amount *= self.data_rate
         };
 
+        // biases send_amount to keep buffer length around `target_backlog`
+        if let Some(target) = self.target_backlog {
+            let error: f64 = self.buffer.len() as f64 - target as f64;
+            self.backlog_integral += error * seconds;
+
+            // prevents windup of integral
+            let limit: f64 = target.max(1) as f64 / BACKLOG_I;
+            self.backlog_integral = self.backlog_integral.clamp(-limit, limit);
+
+            send_amount += (error * BACKLOG_P + self.backlog_integral * BACKLOG_I) * seconds;
+            if send_amount < 0.0 {
+                send_amount = 0.0;
+            }
+        }
+
         self.send_amount_excess += send_amount % 1.0;
         let mut send_amount = send_amount.floor() as usize;
 
         // handle of send_amount_excess
         if self.send_amount_excess >= 1.0 {
-            send_amount += 1;
-            self.send_amount_excess -= 1.0;
+            let whole: f64 = self.send_amount_excess.floor();
+            send_amount += whole as usize;
+            self.send_amount_excess -= whole;
+        }
+
+        if let Some(fraction) = self.max_pop_fraction {
+            let cap = (self.buffer.len() as f64 * fraction.clamp(0.0, 1.0)).ceil() as usize;
+            send_amount = send_amount.min(cap);
+        }
+
+        // rounds down to granularity, remainder gets carried over to next pop
+        if self.granularity > 1 {
+            let available: usize = self.buffer.len() - self.buffer.len() % self.granularity;
+            let remainder: usize = send_amount.min(self.buffer.len()) % self.granularity;
+            if send_amount <= self.buffer.len() {
+                self.send_amount_excess += remainder as f64;
+            }
+            send_amount = (send_amount - remainder).min(available);
         }
 
         let o_buffer: Vec<T>;
@@ -247,19 +391,6 @@ impl<T: Clone> Distributor<T> {
             self.buffer.drain(..);
         }
 
-        // prevents buffer to grow indefinetly, can happeen when
-        // distributor runs for hours
-        if let Some(max_buffer_length) = self.max_buffer_length {
-            let cap: usize = max_buffer_length;
-            if self.buffer.len() > cap && cap != 0 {
-                log::warn!("force reset of distribution buffer");
-                if self.buffer.len() > send_amount {
-                    let oversize: usize = self.buffer.len() - send_amount;
-                    self.buffer.drain(0..oversize);
-                }
-            }
-        }
-
         o_buffer
     }
-}
\ No newline at end of file
+}
@@ -3,23 +3,79 @@
 //! 
 //! dependency of `spectrum`
 //! 
-use rustfft::FftPlanner;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rustfft::num_traits::{Float, FloatConst};
+use rustfft::{Fft, FftNum, FftPlanner};
 pub use rustfft::num_complex::Complex;
 
-pub fn forward(data: &[f32]) -> Vec<Complex<f32>> {
+type PlanCache<F> = HashMap<(usize, bool), Arc<dyn Fft<F>>>;
+type Planner<F> = RefCell<(FftPlanner<F>, PlanCache<F>)>;
+
+thread_local! {
+    // plans are cached per thread and keyed by length and direction (`true` for inverse)
+    static PLANNER: Planner<f32> = RefCell::new((FftPlanner::new(), HashMap::new()));
+    static PLANNER_F64: Planner<f64> = RefCell::new((FftPlanner::new(), HashMap::new()));
+
+    // number of plans that were created on this thread, to verify that cache is used
+    static PLANS_CREATED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// float type FFTs can be computed in, `f32` or `f64`, each with its own plan cache
+pub trait FftFloat: FftNum + Float + FloatConst {
+    /// FFT of `length`, planned once per thread and reused afterwards
+    fn plan(length: usize, inverse: bool) -> Arc<dyn Fft<Self>>;
+}
+impl FftFloat for f32 {
+    fn plan(length: usize, inverse: bool) -> Arc<dyn Fft<f32>> {
+        PLANNER.with(|planner| cached_plan(planner, length, inverse))
+    }
+}
+impl FftFloat for f64 {
+    fn plan(length: usize, inverse: bool) -> Arc<dyn Fft<f64>> {
+        PLANNER_F64.with(|planner| cached_plan(planner, length, inverse))
+    }
+}
+
+fn plan(length: usize, inverse: bool) -> Arc<dyn Fft<f32>> {
+    f32::plan(length, inverse)
+}
+
+fn cached_plan<F: FftNum>(planner: &Planner<F>, length: usize, inverse: bool) -> Arc<dyn Fft<F>> {
+    let (planner, cache) = &mut *planner.borrow_mut();
+    cache
+        .entry((length, inverse))
+        .or_insert_with(|| {
+            PLANS_CREATED.with(|c| c.set(c.get() + 1));
+            if inverse {
+                planner.plan_fft_inverse(length)
+            } else {
+                planner.plan_fft_forward(length)
+            }
+        })
+        .clone()
+}
+
+/// number of FFT plans created on current thread so far
+#[cfg(test)]
+pub(crate) fn plans_created() -> usize {
+    PLANS_CREATED.with(|c| c.get())
+}
+
+/// FFT of `data`, plans get cached per thread so repeated transforms of the same length are cheap
+pub fn forward<F: FftFloat>(data: &[F]) -> Vec<Complex<F>> {
     let length = data.len();
 
     // conversion to complex numbers
-    let mut buffer: Vec<Complex<f32>> = Vec::new();
+    let mut buffer: Vec<Complex<F>> = Vec::new();
     for d in data {
-        buffer.push(Complex{re: *d, im: 0.0});
+        buffer.push(Complex{re: *d, im: F::zero()});
     }
 
-    // creates a planner
-    let mut planner = FftPlanner::<f32>::new();
-
-    // creates a FFT
-    let fft = planner.plan_fft_forward(length);
+    // reuses FFT of same length
+    let fft = F::plan(length, false);
 
     //input.append(&mut data.to_vec());
 
@@ -34,11 +90,8 @@ pub fn inverse(data: &[Complex<f32>]) -> Vec<Complex<f32>> {
     let mut data: Vec<Complex<f32>> = data.to_vec();
 
 
-    // creates a planner
-    let mut planner = FftPlanner::<f32>::new();
-
-    // creates a FFT
-    let fft = planner.plan_fft_inverse(length);
+    // reuses FFT of same length
+    let fft = plan(length, true);
 
 
     fft.process(&mut data);
@@ -46,13 +99,124 @@ pub fn inverse(data: &[Complex<f32>]) -> Vec<Complex<f32>> {
     data.to_vec()
 }
 
-pub fn remove_mirroring(data: &[f32]) -> Vec<f32> {
+/// FFT of real `data`, only the `len / 2 + 1` non-redundant bins are returned
+///
+/// same result as `remove_mirroring(&forward(data))`, but for even lengths
+/// even and odd samples are packed into one complex FFT of half the length, which is about twice as fast
+pub fn forward_real<F: FftFloat>(data: &[F]) -> Vec<Complex<F>> {
+    let len = data.len();
+    if len < 2 || !len.is_multiple_of(2) {
+        return remove_mirroring(&forward(data));
+    }
+    let half = len / 2;
+
+    let mut buffer: Vec<Complex<F>> = data
+        .chunks_exact(2)
+        .map(|c| Complex { re: c[0], im: c[1] })
+        .collect();
+    F::plan(half, false).process(&mut buffer);
+
+    let two = F::one() + F::one();
+    let half_one = F::one() / two;
+    (0..=half)
+        .map(|k| {
+            let z = buffer[k % half];
+            let z_mirror = buffer[(half - k) % half].conj();
+            let even = (z + z_mirror) * half_one;
+            let odd = (z - z_mirror) * Complex { re: F::zero(), im: -half_one };
+            let angle = -two * F::PI() * F::from_usize(k).unwrap_or_else(F::zero) / F::from_usize(len).unwrap_or_else(F::one);
+            let twiddle = Complex::from_polar(F::one(), angle);
+            even + twiddle * odd
+        })
+        .collect()
+}
+
+/// inverse of `forward_real()`, `len` is the length of the original signal
+///
+/// unnormalized like `inverse()`, so result has to be divided by `len`
+pub fn inverse_real(data: &[Complex<f32>], len: usize) -> Vec<f32> {
+    if len < 2 || !len.is_multiple_of(2) || data.len() < len / 2 + 1 {
+        let mut full: Vec<Complex<f32>> = vec![Complex { re: 0.0, im: 0.0 }; len];
+        for (f, d) in full.iter_mut().zip(data.iter()) {
+            *f = *d;
+        }
+        enforce_conjugate_symmetry(&mut full);
+        return inverse(&full).iter().map(|c| c.re).collect();
+    }
+    let half = len / 2;
+
+    let mut buffer: Vec<Complex<f32>> = (0..half)
+        .map(|k| {
+            let x = data[k];
+            let x_mirror = data[half - k].conj();
+            let even = x + x_mirror;
+            let twiddle = Complex::from_polar(1.0, 2.0 * std::f32::consts::PI * k as f32 / len as f32);
+            let odd = (x - x_mirror) * twiddle;
+            even + odd * Complex { re: 0.0, im: 1.0 }
+        })
+        .collect();
+    plan(half, true).process(&mut buffer);
+
+    buffer.iter().flat_map(|c| [c.re, c.im]).collect()
+}
+
+/// same as `forward()` but with f64 precision
+pub fn forward_f64(data: &[f64]) -> Vec<Complex<f64>> {
+    forward(data)
+}
+
+/// mirrors the lower half of spectrum onto the upper half as complex conjugate
+///
+/// makes sure that inverse of spectrum is purely real,
+/// imaginary part of DC and nyquist bin get removed,
+/// works for odd lengths as well
+pub fn enforce_conjugate_symmetry(spectrum: &mut [Complex<f32>]) {
+    let len = spectrum.len();
+    if len == 0 {
+        return;
+    }
+
+    spectrum[0].im = 0.0;
+    for i in 1..=(len - 1) / 2 {
+        spectrum[len - i] = spectrum[i].conj();
+    }
+    // only even lengths have a nyquist bin
+    let half = len / 2;
+    if half * 2 == len {
+        spectrum[half].im = 0.0;
+    }
+}
+
+/// true if `len` has no prime factors other than 2, 3, 5 and 7, which are fast to plan and process
+pub fn is_smooth(len: usize) -> bool {
+    if len == 0 {
+        return true;
+    }
+    let mut n = len;
+    for factor in [2, 3, 5, 7] {
+        while n.is_multiple_of(factor) {
+            n /= factor;
+        }
+    }
+    n == 1
+}
+
+/// smallest length that is at least `len` and `is_smooth()`
+pub fn next_smooth_len(len: usize) -> usize {
+    let mut n = len;
+    while !is_smooth(n) {
+        n += 1;
+    }
+    n
+}
+
+pub fn remove_mirroring<T: Clone>(data: &[T]) -> Vec<T> {
     let len = data.len() / 2 + 1;
     data[..len].to_vec()
 }
 
 /// normalizes complex array to real one
-pub fn normalize(data: &[Complex<f32>]) -> Vec<f32> {
+pub fn normalize<F: FftFloat>(data: &[Complex<F>]) -> Vec<F> {
     let norm = data
         .iter()
         .map(|x| x.norm())
@@ -61,6 +225,11 @@ pub fn normalize(data: &[Complex<f32>]) -> Vec<f32> {
     norm
 }
 
+/// same as `normalize()` but with f64 precision
+pub fn normalize_f64(data: &[Complex<f64>]) -> Vec<f64> {
+    normalize(data)
+}
+
 // only extract real numbers out of complex ones
 pub fn get_real(data: &[Complex<f32>]) -> Vec<f32> {
     let len: f32 = data.len() as f32;
@@ -70,4 +239,153 @@ pub fn get_real(data: &[Complex<f32>]) -> Vec<f32> {
         .collect();
 
     norm
-}
\ No newline at end of file
+}
+
+/// time-stretches `signal` by `stretch` without changing its pitch, using a phase vocoder
+///
+/// `signal` is analysed in hanning windowed frames of `frame` samples every `hop` samples,
+/// phase of every bin gets accumulated according to its measured frequency
+/// and frames get resynthesized every `hop * stretch` samples with overlap-add.
+/// result is `signal.len() * stretch` samples long.
+///
+/// `hop` should be `frame / 4` or less, transients get smeared and at extreme stretch factors
+/// (below 0.5 or above 2.0) the result sounds increasingly phasey and reverberant
+pub fn phase_vocoder(signal: &[f32], stretch: f32, frame: usize, hop: usize) -> Vec<f32> {
+    let out_len: usize = (signal.len() as f32 * stretch.max(0.0)).round() as usize;
+    if frame == 0 || signal.is_empty() || out_len == 0 {
+        return vec![0.0; out_len];
+    }
+    let analysis_hop: usize = hop.max(1);
+    let synthesis_hop: usize = ((analysis_hop as f32 * stretch).round() as usize).max(1);
+
+    // padding at the end, so that the last samples are part of a complete frame
+    let mut padded: Vec<f32> = signal.to_vec();
+    padded.resize(signal.len() + frame, 0.0);
+
+    let mut stft = Stft::new(frame, analysis_hop);
+    let frames = stft.process(&padded);
+    let window = stft.window.clone();
+    let inverse = plan(frame, true);
+
+    let bins: usize = frame / 2 + 1;
+    let omega: Vec<f32> = (0..bins)
+        .map(|k| 2.0 * std::f32::consts::PI * k as f32 / frame as f32)
+        .collect();
+    let mut previous_phase: Vec<f32> = vec![0.0; bins];
+    let mut phase: Vec<f32> = vec![0.0; bins];
+
+    let total_len: usize = frames.len().saturating_sub(1) * synthesis_hop + frame;
+    let mut output: Vec<f32> = vec![0.0; total_len];
+    let mut norm: Vec<f32> = vec![0.0; total_len];
+
+    for (m, spectrum) in frames.iter().enumerate() {
+        let mut buffer: Vec<Complex<f32>> = vec![Complex { re: 0.0, im: 0.0 }; frame];
+        for (k, bin) in spectrum.iter().enumerate() {
+            let measured = bin.arg();
+            if m == 0 {
+                phase[k] = measured;
+            } else {
+                // deviation from expected phase advance, wrapped to -PI..PI
+                let expected = omega[k] * analysis_hop as f32;
+                let mut delta = measured - previous_phase[k] - expected;
+                delta -= 2.0 * std::f32::consts::PI * (delta / (2.0 * std::f32::consts::PI)).round();
+                phase[k] += (omega[k] + delta / analysis_hop as f32) * synthesis_hop as f32;
+            }
+            previous_phase[k] = measured;
+            buffer[k] = Complex::from_polar(bin.norm(), phase[k]);
+        }
+        enforce_conjugate_symmetry(&mut buffer);
+        inverse.process(&mut buffer);
+
+        let start = m * synthesis_hop;
+        for (i, (x, w)) in buffer.iter().zip(window.iter()).enumerate() {
+            output[start + i] += x.re / frame as f32 * w;
+            norm[start + i] += w * w;
+        }
+    }
+
+    for (x, n) in output.iter_mut().zip(norm.iter()) {
+        if *n > 1e-3 {
+            *x /= n;
+        }
+    }
+    output.resize(out_len, 0.0);
+
+    output
+}
+
+/// short time fourier transform over a whole signal
+///
+/// frames of `fft_size` samples are taken every `hop_size` samples and multiplied with `window`,
+/// a signal of length `n` results in `1 + (n - fft_size) / hop_size` frames (0 if `n < fft_size`),
+/// each frame consists of `fft_size / 2 + 1` bins from DC up to nyquist
+///
+/// the fft plan is cached and only recreated if `fft_size` changes
+#[derive(Clone)]
+pub struct Stft {
+    /// must be `fft_size` long
+    pub window: Vec<f32>,
+    pub fft_size: usize,
+    pub hop_size: usize,
+    plan: Arc<dyn Fft<f32>>,
+}
+
+impl Stft {
+    /// stft with hanning window
+    pub fn new(fft_size: usize, hop_size: usize) -> Self {
+        let window = (0..fft_size)
+            .map(|i| {
+                let x = 2.0 * std::f64::consts::PI * i as f64 / (fft_size as f64 - 1.0).max(1.0);
+                (0.5 - 0.5 * x.cos()) as f32
+            })
+            .collect();
+
+        Self::with_window(window, hop_size)
+    }
+
+    /// stft with custom window, `fft_size` is length of window
+    pub fn with_window(window: Vec<f32>, hop_size: usize) -> Self {
+        let fft_size = window.len();
+        let plan = plan(fft_size, false);
+        Stft {
+            window,
+            fft_size,
+            hop_size,
+            plan,
+        }
+    }
+
+    /// number of frames `process()` returns for a signal of `len` samples
+    pub fn frame_count(&self, len: usize) -> usize {
+        if len < self.fft_size || self.fft_size == 0 {
+            return 0;
+        }
+        1 + (len - self.fft_size) / self.hop_size.max(1)
+    }
+
+    /// complex stft matrix of `signal`, indexed as `[frame][bin]`
+    ///
+    /// panics if `window` is not `fft_size` long
+    pub fn process(&mut self, signal: &[f32]) -> Vec<Vec<Complex<f32>>> {
+        assert_eq!(self.window.len(), self.fft_size, "window length must be equal to fft_size");
+        if self.plan.len() != self.fft_size {
+            self.plan = plan(self.fft_size, false);
+        }
+
+        let hop = self.hop_size.max(1);
+        let mut scratch = vec![Complex { re: 0.0, im: 0.0 }; self.plan.get_inplace_scratch_len()];
+        (0..self.frame_count(signal.len()))
+            .map(|frame| {
+                let start = frame * hop;
+                let mut buffer: Vec<Complex<f32>> = signal[start..start + self.fft_size]
+                    .iter()
+                    .zip(self.window.iter())
+                    .map(|(x, w)| Complex { re: x * w, im: 0.0 })
+                    .collect();
+                self.plan.process_with_scratch(&mut buffer, &mut scratch);
+                buffer.truncate(self.fft_size / 2 + 1);
+                buffer
+            })
+            .collect()
+    }
+}
@@ -6,6 +6,7 @@
 //! - [**distributor**](./distributor/index.html) distributes big buffers into smaller ones.
 //!   Results in much smoother output of `distributor` when applied.
 //! - [**audio_capture**](./audio_capture/index.html) captures system audio using [CPAL](https://github.com/RustAudio/cpal).
+//! - [**pipeline**](./pipeline/index.html) wires **audio_capture**, **distributor** and **spectrum** together in one call.
 //!
 //!# Code Example with spectrum
 //!```
@@ -18,11 +19,15 @@
 //! audio_capture.init(&Device::DefaultInput).unwrap();
 //! let audio_receiver = audio_capture.get_receiver().unwrap();
 //!
+//! let sampling_rate = audio_capture.sampling_rate.unwrap();
+//!
 //! // smooths choppy audio data received from audio_receiver
-//! let mut distributor: Distributor<f32> = Distributor::new(44_100.0, Some(8128));
+//! let mut distributor: Distributor<f32> = Distributor::new(sampling_rate as f64, Some(8128));
 //!
-//! // spectrum visualizer stream
-//! let mut stream: Stream = Stream::new(StreamConfig::default()); 
+//! // spectrum visualizer stream, with frequencies matching the sampling rate of captured audio
+//! let mut config = StreamConfig::default();
+//! config.sync_sample_rate(sampling_rate);
+//! let mut stream: Stream = Stream::new(config);
 //! loop {
 //!     if let Some(data) = audio_receiver.receive_data() {
 //!         distributor.push_auto(&data);
@@ -48,6 +53,10 @@ pub mod audio_capture;
 #[cfg(feature = "distributor")]
 pub mod distributor;
 
+/// capture, distributor and stream wired together
+#[cfg(all(feature = "cpal", feature = "distributor", feature = "spectrum"))]
+pub mod pipeline;
+
 #[cfg(feature = "processor")]
 pub mod processor;
 
@@ -64,6 +73,11 @@ mod tests {
     #[cfg(feature = "distributor")]
     use crate::distributor::Distributor;
 
+    // `len` samples of a sine with `freq` hz at sampling rate `rate`
+    fn sine(freq: f32, rate: f32, len: usize) -> Vec<f32> {
+        (0..len).map(|i| (i as f32 / rate * freq * 2.0 * std::f32::consts::PI).sin()).collect()
+    }
+
     #[cfg(feature = "fft")]
     use crate::fft;
 
@@ -74,7 +88,8 @@ mod tests {
             "audio_scope",
             "audio_spectrum",
             "device_selector",
-            "distributor"
+            "distributor",
+            "pipeline"
         ];
 
         for example in examples {
@@ -111,7 +126,7 @@ mod tests {
             }
 
             let data = distributor.pop_auto(None);
-            let buf_len = distributor.clone_buffer().len();
+            let buf_len = distributor.len();
 
             // if sample rate is fully known with 2 pushes
             if counter >= 10 {
@@ -159,5 +174,2792 @@ mod tests {
         )
     }
 
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn chroma() {
+        use crate::spectrum::{config::ProcessorConfig, processor::Processor};
+
+        let buffer: Vec<f32> = sine(440.0, 44_100.0, 4096);
+
+        let mut processor = Processor::from_raw_data(ProcessorConfig::default(), buffer);
+        processor.compute_all();
+        let chroma = processor.chroma();
+
+        // pitch class of A
+        let max = chroma.iter().cloned().fold(0.0, f32::max);
+        assert_eq!(chroma[9], max);
+        assert_eq!(max, 1.0);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn per_band_agc() {
+        use crate::spectrum::{config::{StreamConfig, Interpolation}, stream::Stream};
+
+        let mut config = StreamConfig {
+            channel_count: 1,
+            gravity: None,
+            per_band_agc: Some(0.99),
+            ..Default::default()
+        };
+        config.processor.interpolation = Interpolation::Linear;
+        let fft_res = config.fft_resolution;
+        let mut stream = Stream::new(config);
+
+        // quiet sine at 1khz
+        let mut x: usize = 0;
+        for _ in 0..10 {
+            let mut buffer: Vec<f32> = Vec::new();
+            for _ in 0..=fft_res {
+                let t = x as f32 / 44_100.0;
+                buffer.push((t * 1000.0 * 2.0 * std::f32::consts::PI).sin() * 0.001);
+                x += 1;
+            }
+            stream.push_data(buffer);
+            stream.update();
+        }
+
+        let frequencies = stream.get_frequencies();
+        let max = frequencies[0].iter().map(|f| f.volume).fold(0.0, f32::max);
+        assert!(max > 0.99);
+    }
+
+    #[cfg(feature = "cpal")]
+    #[test]
+    fn capture_mono() {
+        use crate::audio_capture::converter::to_mono;
+
+        // interleaved stereo
+        let data: Vec<f32> = vec![1.0, 0.0, 0.5, 0.5, -1.0, 1.0, 0.2, 0.4];
+        let mono = to_mono(&data, 2);
+
+        assert_eq!(mono.len(), data.len() / 2);
+        assert_eq!(mono, vec![0.5, 0.5, 0.0, 0.3]);
+
+        // captured stereo device reaches receivers as one channel
+        let mut capture = crate::audio_capture::capture::Capture::new();
+        capture.force_mono = true;
+        let mut input = capture.switch_to_synthetic(2, 44_100).unwrap();
+        assert_eq!(capture.channel_count, Some(1));
+        let receiver = capture.get_receiver().unwrap();
+        input.send(&data);
+        assert_eq!(receiver.receive_data(), Some(mono));
+
+        capture.force_mono = false;
+        let mut input = capture.switch_to_synthetic(2, 44_100).unwrap();
+        assert_eq!(capture.channel_count, Some(2));
+        input.send(&data);
+        assert_eq!(receiver.receive_data(), Some(data));
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn spectrum_plugin() {
+        use crate::spectrum::{Frequency, config::ProcessorConfig, plugin::SpectrumPlugin, processor::Processor};
+
+        #[derive(Debug)]
+        struct Halve;
+        impl SpectrumPlugin for Halve {
+            fn apply(&self, freqs: &mut Vec<Frequency>, _config: &ProcessorConfig) {
+                for freq in freqs.iter_mut() {
+                    freq.volume *= 0.5;
+                }
+            }
+        }
+
+        let mut buffer: Vec<f32> = Vec::new();
+        let mut x: f32 = 0.0;
+        for _ in 0..256 {
+            buffer.push(x.sin());
+            x += 0.1;
+        }
+
+        let mut reference = Processor::from_raw_data(ProcessorConfig::default(), buffer.clone());
+        reference.compute_all();
+
+        let mut processor = Processor::from_raw_data(ProcessorConfig::default(), buffer);
+        processor.add_plugin(Box::new(Halve));
+        processor.compute_all();
+
+        assert_eq!(reference.freq_buffer.len(), processor.freq_buffer.len());
+        for (r, p) in reference.freq_buffer.iter().zip(processor.freq_buffer.iter()) {
+            assert!((r.volume * 0.5 - p.volume).abs() < 1e-6);
+        }
+    }
+
+    #[cfg(all(feature = "distributor", feature = "std"))]
+    #[test]
+    fn elapsed_from_duration() {
+        use std::time::Duration;
+        use crate::distributor::{Distributor, Elapsed};
+
+        let mut micros: Distributor<f32> = Distributor::new(1000.0, None);
+        let mut duration: Distributor<f32> = Distributor::new(1000.0, None);
+        for _ in 0..3 {
+            micros.push(&[0.0; 441], Elapsed::Micros(10_000));
+            duration.push(&[0.0; 441], Duration::from_millis(10).into());
+        }
+        assert!((micros.data_rate - duration.data_rate).abs() < 1e-6);
+        assert!((micros.data_rate - 44_100.0).abs() < 1e-6);
+
+        assert!(matches!(Elapsed::from_duration(Duration::from_micros(3)), Elapsed::Nanos(3_000)));
+    }
+
+    #[cfg(feature = "distributor")]
+    #[test]
+    fn distributor_dropped() {
+        use crate::distributor::{Distributor, Elapsed};
+
+        let max: usize = 100;
+        let mut distributor: Distributor<f32> = Distributor::new(1000.0, Some(max));
+        distributor.set_granularity(2);
+
+        for _ in 0..10 {
+            distributor.push(&[0.0; 33], Elapsed::Millis(10));
+            assert!(distributor.buffer.len() <= max);
+        }
+        assert!(distributor.dropped > 0);
+        assert_eq!(distributor.dropped as usize + distributor.buffer.len(), 330);
+
+        let dropped = distributor.dropped;
+        distributor.pop(Elapsed::Millis(10), None);
+        distributor.push(&[0.0; 10], Elapsed::Millis(10));
+        assert_eq!(distributor.dropped, dropped);
+    }
+
+    #[cfg(feature = "distributor")]
+    #[test]
+    fn distributor_drift() {
+        use crate::distributor::Elapsed;
+
+        // producer thinks it pushes 441 samples every 10ms,
+        // but its clock runs 0.1% fast compared to the consumer
+        let push_interval: u128 = 9_990;
+        let target: usize = 2048;
+
+        let mut distributor: Distributor<f32> = Distributor::new(44_100.0, None);
+        distributor.target_backlog = Some(target);
+
+        let mut next_push: u128 = 0;
+        for t in 0..200_000u128 {
+            let now = t * 1000;
+            if now >= next_push {
+                distributor.push(&[0.0; 441], Elapsed::Micros(10_000));
+                next_push += push_interval;
+            }
+            distributor.pop(Elapsed::Micros(1000), None);
+
+            // after settling
+            if t > 10_000 {
+                assert!(distributor.buffer.len() < target * 2);
+            }
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn mid_side() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+
+        let config = StreamConfig::default();
+        let fft_res = config.fft_resolution;
+
+        let sine: Vec<f32> = sine(1000.0, 44_100.0, fft_res);
+        let max = |freqs: &[crate::spectrum::Frequency]| freqs.iter().map(|f| f.volume).fold(0.0, f32::max);
+
+        // centered
+        let mut stream = Stream::new(config.clone());
+        stream.push_data(sine.iter().flat_map(|s| vec![*s, *s]).collect());
+        let (mid, side) = stream.get_mid_side_frequencies().unwrap();
+        assert!(max(&mid) > 0.1);
+        assert_eq!(max(&side), 0.0);
+
+        // only left
+        let mut stream = Stream::new(config);
+        stream.push_data(sine.iter().flat_map(|s| vec![*s, 0.0]).collect());
+        let (_, side) = stream.get_mid_side_frequencies().unwrap();
+        assert!(max(&side) > 0.1);
+
+        // mono stream
+        let mut stream = Stream::new(StreamConfig { channel_count: 1, ..Default::default() });
+        stream.push_data(sine);
+        assert!(stream.get_mid_side_frequencies().is_none());
+    }
+
+    #[cfg(feature = "cpal")]
+    #[test]
+    fn capture_device_name() {
+        use cpal::traits::{DeviceTrait, HostTrait};
+        use crate::audio_capture::capture::{Capture, Device};
+
+        let mut capture = Capture::new();
+        assert!(capture.init(&Device::Name(String::from("no device has this name"))).is_err());
+
+        // requires audio hardware, default device is not always listed in `fetch_devices()`, on ALSA for example
+        let name = match cpal::default_host().default_input_device().and_then(|d| d.name().ok()) {
+            Some(name) => name,
+            None => return,
+        };
+        if capture.fetch_devices().unwrap_or_default().contains(&name) {
+            capture.init(&Device::Name(name)).unwrap();
+            assert!(capture.sampling_rate.is_some());
+        }
+    }
+
+    #[cfg(feature = "cpal")]
+    #[test]
+    fn capture_pause() {
+        use crate::audio_capture::capture::Capture;
+
+        let mut capture = Capture::new();
+        assert!(capture.pause().is_ok());
+        assert!(capture.resume().is_ok());
+
+        let mut input = capture.switch_to_synthetic(1, 44_100).unwrap();
+        let receiver = capture.get_receiver().unwrap();
+        input.send(&[0.1, 0.2]);
+        assert_eq!(receiver.receive_data(), Some(vec![0.1, 0.2]));
+
+        // buffered data is discarded and data of callback dropped while paused
+        input.send(&[0.3]);
+        capture.pause().unwrap();
+        input.send(&[0.4]);
+        assert!(receiver.receive_data().is_none());
+        capture.resume().unwrap();
+        assert!(receiver.receive_data().is_none());
+        input.send(&[0.5]);
+        assert_eq!(receiver.receive_data(), Some(vec![0.5]));
+
+        // stop releases device, receiver gets reattached on next start
+        input.send(&[0.6]);
+        capture.stop();
+        assert!(capture.sampling_rate.is_none());
+        assert!(capture.channel_count.is_none());
+        assert!(receiver.receive_data().is_none());
+        let mut input = capture.switch_to_synthetic(1, 44_100).unwrap();
+        input.send(&[0.7]);
+        assert_eq!(receiver.receive_data(), Some(vec![0.7]));
+    }
+
+    #[cfg(feature = "cpal")]
+    #[test]
+    fn capture_sampling_rate() {
+        use crate::audio_capture::capture::{Capture, Device};
+
+        let mut capture = Capture::new();
+        assert!(capture.sampling_rate.is_none());
+
+        // requires audio hardware
+        if capture.init(&Device::DefaultInput).is_ok() {
+            assert!(capture.sampling_rate.is_some());
+            assert!(capture.channel_count.is_some());
+        }
+    }
+
+    // manual test, play something on the default output device while running
+    // `cargo test capture_loopback -- --ignored`, only passes on loopback capable hosts like WASAPI
+    #[cfg(feature = "cpal")]
+    #[test]
+    #[ignore]
+    fn capture_loopback() {
+        use crate::audio_capture::capture::{Capture, Device};
+
+        let mut capture = Capture::new();
+        assert!(capture.init(&Device::OutputId(usize::MAX)).is_err());
+        capture.init(&Device::DefaultOutput).unwrap();
+        let receiver = capture.get_receiver().unwrap();
+
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        let data = receiver.receive_data().unwrap();
+        assert!(data.iter().any(|x| x.abs() > 0.0));
+    }
+
+    #[cfg(feature = "cpal")]
+    #[test]
+    fn capture_switch_device() {
+        use crate::audio_capture::capture::{Capture, Device};
+
+        let mut capture = Capture::new();
+        assert!(capture.switch_device(&Device::Id(usize::MAX)).is_err());
+
+        assert!(capture.sampling_rate.is_none());
+
+        let mut input = capture.switch_to_synthetic(2, 44_100).unwrap();
+        let receiver = capture.get_receiver().unwrap();
+        input.send(&[0.1, 0.2]);
+
+        // receiver stays connected to event handler and gets data of both devices
+        drop(input);
+        let mut input = capture.switch_to_synthetic(1, 48_000).unwrap();
+        assert_eq!(capture.channel_count, Some(1));
+        assert_eq!(capture.sampling_rate, Some(48_000));
+        input.send(&[0.3]);
+        assert_eq!(receiver.receive_data(), Some(vec![0.1, 0.2, 0.3]));
+        input.send(&[0.4]);
+        assert_eq!(receiver.receive_data(), Some(vec![0.4]));
+
+        // failed switch does not report config of previous device
+        assert!(capture.switch_device(&Device::Id(usize::MAX)).is_err());
+        assert!(capture.sampling_rate.is_none());
+        assert!(capture.channel_count.is_none());
+    }
+
+    #[cfg(feature = "processor")]
+    #[test]
+    fn filter_latency() {
+        use crate::processor::{Processor, Plugin, Lowpass};
+
+        let mut data: Vec<f32> = vec![0.0; 1024];
+        data[100] = 1.0;
+
+        let mut processor = Processor {
+            data,
+            sampling_rate: 44_100.0,
+            plugins: vec![Plugin::Lowpass(Lowpass::new(1000.0, 2000.0))],
+        };
+        let latency = processor.latency();
+        processor.process();
+
+        let peak = processor.data
+            .iter()
+            .enumerate()
+            .fold((0, 0.0), |max, (i, v)| if *v > max.1 { (i, *v) } else { max })
+            .0;
+
+        assert_eq!(peak, 100 + latency.group_delay);
+        assert_eq!(latency.block_delay, 1024);
+    }
+
+    #[cfg(feature = "processor")]
+    #[test]
+    fn biquad_latency() {
+        use crate::processor::{Processor, Plugin, Lowpass, Biquad};
+
+        let mut data: Vec<f32> = vec![0.0; 1024];
+        data[100] = 1.0;
+
+        let mut processor = Processor {
+            data,
+            sampling_rate: 44_100.0,
+            plugins: vec![Plugin::Lowpass(Lowpass::new(4000.0, 6000.0)), Plugin::Biquad(Biquad::new(1000.0, 0.707))],
+        };
+        let latency = processor.latency();
+        assert!(latency.group_delay > 5);
+        processor.process();
+
+        // centroid of impulse response, which is where low frequencies are delayed to
+        let sum: f32 = processor.data.iter().sum();
+        let centroid: f32 = processor.data.iter().enumerate().map(|(i, v)| i as f32 * v).sum::<f32>() / sum;
+        assert!((centroid - (100 + latency.group_delay) as f32).abs() <= 0.5, "{} != {}", centroid, 100 + latency.group_delay);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn volume_stats() {
+        use crate::spectrum::{Frequency, stats::VolumeStats};
+
+        let mut stats = VolumeStats::new(10, 100, 1.0);
+        assert_eq!(stats.percentile(0.5), 0.0);
+
+        // evenly distributed volumes in range (0..1)
+        let frame: Vec<Frequency> = (0..1000)
+            .map(|i| Frequency { volume: i as f32 / 1000.0, ..Frequency::empty() })
+            .collect();
+        for _ in 0..20 {
+            stats.push(&frame);
+        }
+
+        assert!((stats.percentile(0.5) - 0.5).abs() < 0.01);
+        assert!((stats.percentile(0.95) - 0.95).abs() < 0.01);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn processor64() {
+        use crate::spectrum::{config::{ProcessorConfig, Interpolation}, processor::Processor, processor64::Processor64};
+
+        let config = ProcessorConfig {
+            interpolation: Interpolation::Linear,
+            ..Default::default()
+        };
+        let len: usize = 1 << 17;
+        let buffer: Vec<f64> = (0..len).map(|i| (i as f64 * 0.1).sin()).collect();
+
+        let mut p32 = Processor::from_raw_data(config.clone(), buffer.iter().map(|x| *x as f32).collect());
+        p32.fft();
+        p32.raw_to_freq_buffer();
+        p32.normalize_frequency_position();
+
+        let mut p64 = Processor64::from_raw_data(config.clone(), buffer);
+        p64.fft();
+        p64.raw_to_freq_buffer();
+        p64.normalize_frequency_position();
+
+        // harmonic positions accumulate error over many frequencies
+        let bins = p64.freq_buffer.len();
+        let harmonic = |n: usize| -> f64 {
+            let mut sum: f64 = 0.0;
+            for i in (1..=n).rev() {
+                sum += 1.0 / i as f64;
+            }
+            sum
+        };
+        let expected = harmonic(bins / 2) / harmonic(bins - 1);
+        let err32 = (p32.freq_buffer[bins / 2].position as f64 - expected).abs();
+        let err64 = (p64.freq_buffer[bins / 2].position - expected).abs();
+        assert!(err64 < err32);
+        assert!(err64 < 1e-9);
+
+        // full pipeline stays close to f32 output
+        let mut p32 = Processor::from_raw_data(config.clone(), (0..4096).map(|i| (i as f32 * 0.1).sin()).collect());
+        p32.compute_all();
+        let mut p64 = Processor64::from_raw_data(config, (0..4096).map(|i| (i as f64 * 0.1).sin()).collect());
+        p64.compute_all();
+        assert_eq!(p32.freq_buffer.len(), p64.freq_buffer.len());
+        for (a, b) in p32.freq_buffer.iter().zip(p64.freq_buffer.iter()) {
+            assert!((a.volume as f64 - b.volume).abs() < 1e-2);
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn processor64_parity() {
+        use crate::spectrum::{
+            config::{ProcessorConfig, Interpolation, FrequencyAxis, VolumeScale, Window},
+            processor::Processor,
+            processor64::Processor64,
+        };
+
+        let configs = [
+            ProcessorConfig::default(),
+            ProcessorConfig {
+                window: Window::Blackman,
+                volume_scale: VolumeScale::Decibel { floor_db: -60.0 },
+                calibration: Some(0.5),
+                suppress_frequencies: vec![(1000.0, 200.0)],
+                restrict_to_bounds: true,
+                pinned_frequencies: vec![440.0],
+                interpolation: Interpolation::Cubic,
+                cubic_tension: 0.5,
+                resolution: Some(64),
+                ..Default::default()
+            },
+            ProcessorConfig {
+                window: Window::Rectangular,
+                calibration: Some(2.0),
+                restrict_to_bounds: true,
+                frequency_axis: FrequencyAxis::Harmonic,
+                interpolation: Interpolation::None,
+                ..Default::default()
+            },
+            ProcessorConfig {
+                real_fft: true,
+                manual_position_distribution: Some(vec![(0, 2.0), (2000, 1.0), (22_050, 0.5)]),
+                ..Default::default()
+            },
+        ];
+        // not 7-smooth, so both get zero-padded
+        let data: Vec<f64> = (0..2041)
+            .map(|i| {
+                let t = i as f64 / 44_100.0 * 2.0 * std::f64::consts::PI;
+                (t * 440.0).sin() + 0.5 * (t * 1000.0).sin() + 0.25 * (t * 3000.0).sin()
+            })
+            .collect();
+
+        for config in configs {
+            let mut p32 = Processor::from_raw_data(config.clone(), data.iter().map(|x| *x as f32).collect());
+            p32.compute_all();
+            let mut p64 = Processor64::from_raw_data(config.clone(), data.clone());
+            p64.compute_all();
+
+            assert_eq!(p32.freq_buffer.len(), p64.freq_buffer.len(), "{:?}", config);
+            let max = p64.freq_buffer.iter().map(|f| f.volume).fold(0.0, f64::max);
+            for (a, b) in p32.freq_buffer.iter().zip(p64.freq_buffer.iter()) {
+                assert!((a.volume as f64 - b.volume).abs() < max * 1e-3, "{:?}: {:?} != {:?}", config, a, b);
+                assert!((a.freq as f64 - b.freq).abs() < b.freq * 1e-4, "{:?}: {:?} != {:?}", config, a, b);
+                assert!((a.position as f64 - b.position).abs() < 1e-4, "{:?}: {:?} != {:?}", config, a, b);
+            }
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_on_frame() {
+        use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+
+        let config = StreamConfig { channel_count: 1, ..Default::default() };
+        let fft_res = config.fft_resolution;
+        let mut stream = Stream::new(config);
+
+        let frames = Arc::new(AtomicUsize::new(0));
+        let counter = frames.clone();
+        stream.set_on_frame(move |freqs| {
+            assert_eq!(freqs.len(), 1);
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        // not enough data for FFT
+        stream.update();
+        stream.push_data(vec![0.0; fft_res / 2]);
+        stream.update();
+        assert_eq!(frames.load(Ordering::SeqCst), 0);
+
+        // only first update has new data to compute
+        stream.push_data(vec![0.0; fft_res]);
+        stream.update();
+        stream.update();
+        assert_eq!(frames.load(Ordering::SeqCst), 1);
+
+        stream.push_data(vec![0.0; 1]);
+        stream.update();
+        assert_eq!(frames.load(Ordering::SeqCst), 2);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn sync_sample_rate() {
+        use crate::spectrum::{config::{ProcessorConfig, StreamConfig}, stream::Stream};
+
+        let mut config = StreamConfig {
+            per_channel_processor: Some(vec![ProcessorConfig::default()]),
+            ..Default::default()
+        };
+        assert_eq!(config.processor.sampling_rate, 44_100);
+        config.sync_sample_rate(48_000);
+        assert_eq!(config.processor.sampling_rate, 48_000);
+        assert_eq!(config.processor_for(0).sampling_rate, 48_000);
+        assert_eq!(config.processor_for(1).sampling_rate, 48_000);
+
+        let mut stream = Stream::new(config);
+        assert!(stream.check_sample_rate(48_000));
+        assert!(!stream.check_sample_rate(44_100));
+        assert!(!stream.check_sample_rate(44_100));
+
+        stream.config.per_channel_processor = Some(vec![ProcessorConfig::default()]);
+        assert!(!stream.check_sample_rate(48_000));
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_push_magnitudes() {
+        use crate::spectrum::{config::StreamConfig, processor::Processor, stream::Stream};
+
+        let config = StreamConfig { channel_count: 1, ..Default::default() };
+        let fft_res = config.fft_resolution;
+        let signal: Vec<f32> = (0..fft_res).map(|i| (i as f32 * 0.07).sin() + 0.5 * (i as f32 * 0.4).sin()).collect();
+
+        let mut time_domain = Stream::new(config.clone());
+        time_domain.push_data(vec![0.0]);
+        time_domain.push_data(signal.clone());
+        time_domain.update();
+
+        let mut transform = Processor::from_raw_data(config.processor.clone(), signal);
+        transform.apodize();
+        transform.fft();
+
+        let mut frequency_domain = Stream::new(config);
+        frequency_domain.push_magnitudes(vec![]);
+        frequency_domain.update();
+        assert!(frequency_domain.is_starved());
+        frequency_domain.push_magnitudes(vec![transform.raw_bins().to_vec()]);
+        frequency_domain.update();
+
+        let (a, b) = (&time_domain.get_frequencies()[0], &frequency_domain.get_frequencies()[0]);
+        assert!(!a.is_empty());
+        assert_eq!(a.len(), b.len());
+        assert!(a.iter().zip(b.iter()).all(|(a, b)| a.volume == b.volume && a.freq == b.freq));
+
+        // every frame advances the clock by its fft size
+        let rate = frequency_domain.config.processor.sampling_rate as f64;
+        let (time, _) = frequency_domain.get_frequencies_timed();
+        assert_eq!(time, std::time::Duration::from_secs_f64(fft_res as f64 / rate));
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[cfg(feature = "std")]
+    #[test]
+    fn stream_push_magnitudes_rate_limit() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+
+        let config = StreamConfig { channel_count: 1, max_fft_rate: Some(10.0), gravity: None, ..Default::default() };
+        let len = config.fft_resolution / 2 + 1;
+        let mut stream = Stream::new(config);
+
+        stream.push_magnitudes(vec![vec![0.0; len]]);
+        stream.update();
+        let silent = stream.get_frequencies();
+
+        // frame is pushed while rate limit is active, so it gets processed by the update after it
+        stream.push_magnitudes(vec![vec![1.0; len]]);
+        stream.update();
+        assert!(stream.get_frequencies()[0].iter().zip(silent[0].iter()).all(|(a, b)| a.volume == b.volume));
+
+        std::thread::sleep(std::time::Duration::from_millis(120));
+        stream.update();
+        assert!(stream.get_frequencies()[0].iter().any(|f| f.volume > 0.0));
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_push_channels() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+        use crate::utils::interleaved_to_planar;
+
+        let config = StreamConfig::default();
+        let fft_res = config.fft_resolution;
+        let interleaved: Vec<f32> = (0..(fft_res + 1) * 2)
+            .map(|i| ((i / 2) as f32 * if i % 2 == 0 { 0.05 } else { 0.13 }).sin())
+            .collect();
+
+        let mut a = Stream::new(config.clone());
+        a.push_data(interleaved.clone());
+        a.update();
+
+        let mut b = Stream::new(config);
+        b.push_channels(&[vec![0.0; 3]]);
+        assert_eq!(b.buffered_len(), 0);
+        b.push_channels(&[vec![0.0; 3], vec![0.0; 2]]);
+        assert_eq!(b.buffered_len(), 0);
+        b.push_channels(&interleaved_to_planar(&interleaved, 2));
+        b.update();
+
+        let (a, b) = (a.get_frequencies(), b.get_frequencies());
+        assert_eq!(a.len(), 2);
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert_eq!(a.len(), b.len());
+            assert!(a.iter().zip(b.iter()).all(|(a, b)| a.volume == b.volume && a.freq == b.freq));
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_gravity_floor() {
+        use crate::spectrum::{config::{StreamConfig, Interpolation}, stream::Stream};
+
+        let settle = |gravity_floor: f32| -> Vec<f32> {
+            let mut config = StreamConfig { channel_count: 1, gravity_floor, ..Default::default() };
+            // cubic interpolation overshoots at the edges
+            config.processor.interpolation = Interpolation::None;
+            let fft_res = config.fft_resolution;
+            let mut stream = Stream::new(config);
+
+            let tone: Vec<f32> = sine(440.0, 44_100.0, fft_res);
+            stream.push_data(tone);
+            stream.update();
+
+            for _ in 0..200 {
+                stream.push_data(vec![0.0; fft_res / 8]);
+                stream.update();
+            }
+            stream.get_frequencies()[0].iter().map(|f| f.volume).collect()
+        };
+
+        assert!(settle(0.0).iter().all(|v| v.abs() < 1e-4));
+        assert!(settle(0.05).iter().all(|v| (v - 0.05).abs() < 1e-4));
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn analyze_trim_silence() {
+        use std::time::Duration;
+        use crate::spectrum::{analyze, config::{ProcessorConfig, AnalyzeConfig}};
+
+        let window_size: usize = 1024;
+
+        // 2 windows of silence, 3 windows of sine, 2 windows of silence
+        let mut data: Vec<f32> = vec![0.0; window_size * 2];
+        for i in 0..window_size * 3 {
+            data.push((i as f32 * 0.1).sin());
+        }
+        data.append(&mut vec![0.0; window_size * 2]);
+
+        let config = ProcessorConfig::default();
+        let untrimmed = analyze(&data, &config, &AnalyzeConfig { window_size, trim_silence: None, ..Default::default() });
+        assert_eq!(untrimmed.len(), 7);
+
+        let trimmed = analyze(&data, &config, &AnalyzeConfig { window_size, trim_silence: Some(0.01), ..Default::default() });
+        assert_eq!(trimmed.len(), 3);
+
+        let window_duration = window_size as f64 / config.sampling_rate as f64;
+        assert_eq!(trimmed[0].0, Duration::from_secs_f64(window_duration * 2.0));
+        assert_eq!(trimmed[2].0, Duration::from_secs_f64(window_duration * 4.0));
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn conjugate_symmetry() {
+        for len in [1024, 1023] {
+            let data: Vec<f32> = (0..len).map(|i| (i as f32 * 0.1).sin() + (i as f32 * 0.73).cos()).collect();
+
+            // scales lower half with arbitrary gains like the filters do
+            let mut spectrum = fft::forward(&data);
+            for (i, bin) in spectrum.iter_mut().enumerate().take(len / 2 + 1) {
+                *bin *= (i as f32 * 0.05).cos().abs();
+            }
+            fft::enforce_conjugate_symmetry(&mut spectrum);
+
+            let inverse = fft::inverse(&spectrum);
+            let max_im = inverse.iter().map(|c| c.im.abs()).fold(0.0, f32::max);
+            let max_re = inverse.iter().map(|c| c.re.abs()).fold(0.0, f32::max);
+            assert!(max_im < max_re * 1e-5);
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[cfg(feature = "std")]
+    #[test]
+    fn stream_max_fft_rate() {
+        use std::{time::Duration, thread::sleep};
+        use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+
+        let config = StreamConfig {
+            channel_count: 1,
+            max_fft_rate: Some(5.0),
+            ..Default::default()
+        };
+        let fft_res = config.fft_resolution;
+        let mut stream = Stream::new(config);
+
+        let frames = Arc::new(AtomicUsize::new(0));
+        let counter = frames.clone();
+        stream.set_on_frame(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        stream.push_data(vec![1.0; fft_res]);
+        for _ in 0..20 {
+            stream.push_data(vec![1.0; 64]);
+            stream.update();
+        }
+        assert_eq!(frames.load(Ordering::SeqCst), 1);
+
+        sleep(Duration::from_millis(200));
+        stream.push_data(vec![1.0; 64]);
+        stream.update();
+        assert_eq!(frames.load(Ordering::SeqCst), 2);
+    }
+
+    #[cfg(feature = "distributor")]
+    #[test]
+    fn distributor_rate_stability() {
+        use crate::distributor::Elapsed;
+
+        let mut steady: Distributor<f32> = Distributor::new(44_100.0, None);
+        let mut jittery: Distributor<f32> = Distributor::new(44_100.0, None);
+        assert_eq!(steady.rate_stability(), 0.0);
+
+        for i in 0..32 {
+            steady.push(&[0.0; 441], Elapsed::Micros(10_000));
+            let elapsed = if i % 2 == 0 { 4_000 } else { 16_000 };
+            jittery.push(&[0.0; 441], Elapsed::Micros(elapsed));
+        }
+
+        assert!(steady.rate_stability() > 0.99);
+        assert!(jittery.rate_stability() < 0.7);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn goertzel() {
+        use crate::spectrum::goertzel::detect;
+
+        // DTMF tone of key "1"
+        let sample_rate: f32 = 8000.0;
+        let data: Vec<f32> = sine(697.0, sample_rate, 2048)
+            .iter()
+            .zip(sine(1209.0, sample_rate, 2048))
+            .map(|(a, b)| 0.5 * a + 0.5 * b)
+            .collect();
+
+        let rows = detect(&data, sample_rate, &[697.0, 770.0, 852.0, 941.0]);
+        let cols = detect(&data, sample_rate, &[1209.0, 1336.0, 1477.0]);
+
+        assert!((rows[0] - 0.5).abs() < 0.05);
+        assert!((cols[0] - 0.5).abs() < 0.05);
+        assert!(rows[1..].iter().all(|v| *v < 0.1));
+        assert!(cols[1..].iter().all(|v| *v < 0.1));
+    }
+
+    #[cfg(feature = "cpal")]
+    #[test]
+    fn capture_target_rms() {
+        use crate::audio_capture::converter::Normalizer;
+
+        let mut normalizer = Normalizer::new(0.5);
+
+        // quiet input with rms of ~0.007
+        let mut rms: f32 = 0.0;
+        for _ in 0..200 {
+            let mut block: Vec<f32> = (0..512).map(|i| (i as f32 * 0.1).sin() * 0.01).collect();
+            normalizer.process(&mut block);
+            rms = (block.iter().map(|x| x * x).sum::<f32>() / block.len() as f32).sqrt();
+        }
+
+        assert!((rms - 0.5).abs() < 0.05);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn frequencies_as_u8() {
+        use crate::spectrum::{Frequency, frequencies_as_u8, config::StreamConfig, stream::Stream};
+
+        let freqs: Vec<Frequency> = [0.0, 0.5, 1.0, 2.0, -1.0]
+            .iter()
+            .map(|v| Frequency { volume: *v, ..Frequency::empty() })
+            .collect();
+
+        assert_eq!(frequencies_as_u8(&freqs, 1.0), vec![0, 128, 255, 255, 0]);
+        assert!(frequencies_as_u8(&freqs, 2.0)[1] > 128);
+
+        let mut stream = Stream::new(StreamConfig::default());
+        stream.push_data(vec![0.0; stream.config.fft_resolution * 4]);
+        stream.update();
+        assert!(stream.frame_as_u8(1, 1.0).is_some());
+        assert!(stream.frame_as_u8(2, 1.0).is_none());
+    }
+
+    #[cfg(feature = "distributor")]
+    #[test]
+    fn distributor_max_pop_fraction() {
+        use crate::distributor::Elapsed;
+
+        let mut distributor: Distributor<f32> = Distributor::new(44_100.0, None);
+        distributor.max_pop_fraction = Some(0.5);
+        distributor.push(&[0.0; 1000], Elapsed::Millis(10));
+
+        // way too high data rate
+        let data = distributor.pop(Elapsed::Millis(10), Some(1_000_000.0));
+        assert_eq!(data.len(), 500);
+        let data = distributor.pop(Elapsed::Millis(10), Some(1_000_000.0));
+        assert_eq!(data.len(), 250);
+        assert_eq!(distributor.buffer.len(), 250);
+    }
+
+    #[test]
+    fn frame() {
+        use crate::utils::{frame, FrameTail};
+
+        let data: Vec<f32> = (0..8).map(|x| x as f32).collect();
+
+        // exact fit
+        let frames = frame(&data, 4, 4, FrameTail::ZeroPad);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(&*frames[1], &[4.0, 5.0, 6.0, 7.0]);
 
-}
\ No newline at end of file
+        // overlapping
+        let frames = frame(&data, 4, 2, FrameTail::Drop);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(&*frames[1], &[2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(&*frames[2], &[4.0, 5.0, 6.0, 7.0]);
+
+        // ragged tail
+        let frames = frame(&data, 3, 3, FrameTail::Drop);
+        assert_eq!(frames.len(), 2);
+        let frames = frame(&data, 3, 3, FrameTail::ZeroPad);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(&*frames[2], &[6.0, 7.0, 0.0]);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn pitch_tracker() {
+        use crate::spectrum::pitch::PitchTracker;
+
+        let sample_rate: f32 = 44_100.0;
+        let mut tracker = PitchTracker::new(sample_rate, 5);
+        assert!(tracker.pitch_hz().is_none());
+
+        let mut x: usize = 0;
+        for _ in 0..8 {
+            let frame: Vec<f32> = (0..4096)
+                .map(|_| {
+                    x += 1;
+                    (x as f32 / sample_rate * 440.0 * 2.0 * std::f32::consts::PI).sin()
+                })
+                .collect();
+            tracker.push(&frame);
+        }
+
+        assert!(tracker.cents_off(69.0).unwrap().abs() < 5.0);
+
+        // fundamental with stronger second harmonic must not be reported an octave too high
+        let mut tracker = PitchTracker::new(sample_rate, 1);
+        let frame: Vec<f32> = (0..4096)
+            .map(|i| {
+                let t = i as f32 / sample_rate * 2.0 * std::f32::consts::PI;
+                0.5 * (t * 220.0).sin() + (t * 440.0).sin()
+            })
+            .collect();
+        tracker.push(&frame);
+        assert!((tracker.pitch_hz().unwrap() - 220.0).abs() < 2.0);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_interleaved() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+
+        let config = StreamConfig { gravity: None, ..Default::default() };
+        let fft_res = config.fft_resolution;
+
+        let mut stream = Stream::new(config);
+        let mut data: Vec<f32> = Vec::new();
+        for x in sine(1000.0, 44_100.0, fft_res + 1) {
+            data.push(x);
+            data.push(0.0);
+        }
+        stream.push_data(data);
+        stream.update();
+
+        let channels = stream.get_frequencies();
+        let interleaved = stream.get_frequencies_interleaved();
+        assert_eq!(interleaved.len(), channels[0].len() * 2);
+        for (i, freq) in interleaved.iter().enumerate() {
+            assert_eq!(freq.volume, channels[i % 2][i / 2].volume);
+        }
+        assert!(interleaved.iter().step_by(2).any(|f| f.volume > 0.0));
+        assert!(interleaved.iter().skip(1).step_by(2).all(|f| f.volume == 0.0));
+    }
+
+    #[cfg(feature = "processor")]
+    #[test]
+    fn notch_filter() {
+        use crate::processor::{Processor, Plugin, Notch};
+        use crate::fft;
+
+        // 1hz per bin
+        let sampling_rate: f32 = 8000.0;
+        let data: Vec<f32> = (0..8000)
+            .map(|i| {
+                let t = i as f32 / sampling_rate * 2.0 * std::f32::consts::PI;
+                (t * 60.0).sin() + (t * 1000.0).sin()
+            })
+            .collect();
+        let magnitude = |data: &[f32], bin: usize| fft::forward(data)[bin].norm();
+
+        let mut processor = Processor {
+            data: data.clone(),
+            sampling_rate,
+            plugins: vec![Plugin::Notch(Notch::new(50.0, 55.0, 65.0, 70.0))],
+        };
+        processor.process();
+
+        assert!(magnitude(&processor.data, 60) < magnitude(&data, 60) * 0.001);
+        assert!((magnitude(&processor.data, 1000) / magnitude(&data, 1000) - 1.0).abs() < 0.01);
+    }
+
+    #[cfg(feature = "processor")]
+    #[test]
+    fn peak_filter() {
+        use crate::processor::{Processor, Plugin};
+
+        let sampling_rate: f32 = 8000.0;
+        let tone = |freq: f32| -> Vec<f32> {
+            sine(freq, sampling_rate, 8000)
+        };
+        let peak = |data: &[f32]| data.iter().fold(0.0_f32, |max, x| max.max(x.abs()));
+
+        for (freq, gain) in [(1000.0, 10_f32.powf(6.0 / 20.0)), (3000.0, 1.0)] {
+            let mut processor = Processor {
+                data: tone(freq),
+                sampling_rate,
+                plugins: vec![Plugin::Peak { center_freq: 1000.0, bandwidth: 100.0, gain_db: 6.0 }],
+            };
+            processor.process();
+            assert!((peak(&processor.data) - gain).abs() < 0.01, "{}: {}", freq, peak(&processor.data));
+        }
+    }
+
+    #[cfg(feature = "processor")]
+    #[test]
+    fn reset_filters() {
+        use crate::processor::{Processor, Plugin, Biquad};
+
+        let mut processor = Processor {
+            data: vec![1.0; 1024],
+            sampling_rate: 44_100.0,
+            plugins: vec![Plugin::Biquad(Biquad::new(1000.0, 0.707))],
+        };
+        processor.process();
+        assert!((processor.data[1023] - 1.0).abs() < 0.01);
+
+        // state of previous block carries over into silence
+        processor.data = vec![0.0; 1024];
+        processor.process();
+        assert!(processor.data[0].abs() > 0.5);
+
+        // fresh start after reset
+        processor.data = vec![1.0; 1024];
+        processor.process();
+        processor.reset_filters();
+        processor.data = vec![0.0; 1024];
+        processor.process();
+        assert!(processor.data.iter().all(|x| *x == 0.0));
+    }
+
+    #[test]
+    fn downmix_surround() {
+        use crate::utils::{downmix_surround, ChannelLayout};
+
+        let silent = vec![0.0; 4];
+        let full = vec![1.0; 4];
+
+        // center at -3dB
+        let mut channels = vec![silent.clone(); 6];
+        channels[2] = full.clone();
+        let mono = downmix_surround(&channels, ChannelLayout::FivePointOne);
+        assert_eq!(mono.len(), 4);
+        assert!(mono.iter().all(|x| (x - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6));
+
+        // LFE dropped
+        let mut channels = vec![silent.clone(); 6];
+        channels[3] = full.clone();
+        let mono = downmix_surround(&channels, ChannelLayout::FivePointOne);
+        assert!(mono.iter().all(|x| *x == 0.0));
+
+        // equal signal on front channels keeps its level
+        let mono = downmix_surround(&[full.clone(), full], ChannelLayout::Stereo);
+        assert!(mono.iter().all(|x| *x == 1.0));
+    }
+
+    #[cfg(feature = "distributor")]
+    #[test]
+    fn distributor_len() {
+        let mut distributor: Distributor<f32> = Distributor::new(1000.0, None);
+        assert!(distributor.is_empty());
+
+        distributor.push(&[0.0; 64], crate::distributor::Elapsed::Millis(64));
+        assert_eq!(distributor.len(), distributor.clone_buffer().len());
+        assert_eq!(distributor.len(), 64);
+
+        distributor.pop(crate::distributor::Elapsed::Millis(16), None);
+        assert_eq!(distributor.len(), distributor.clone_buffer().len());
+        assert!(!distributor.is_empty());
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn volume_at() {
+        use crate::spectrum::{config::ProcessorConfig, processor::Processor};
+
+        let buffer: Vec<f32> = sine(1000.0, 44_100.0, 4096);
+
+        let mut processor = Processor::from_raw_data(ProcessorConfig::default(), buffer);
+        processor.compute_all();
+
+        let bar = processor.freq_buffer[processor.freq_buffer.len() / 2].clone();
+        assert_eq!(processor.volume_at(bar.freq), bar.volume);
+
+        // between two bars
+        let low = processor.freq_buffer[10].clone();
+        let high = processor.freq_buffer[11].clone();
+        let volume = processor.volume_at((low.freq + high.freq) / 2.0);
+        assert!(volume >= low.volume.min(high.volume) && volume <= low.volume.max(high.volume));
+
+        // out of bounds
+        assert_eq!(processor.volume_at(0.0), 0.0);
+        assert_eq!(processor.volume_at(1_000_000.0), 0.0);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn render_spectrogram() {
+        use crate::spectrum::{Frequency, render::{spectrogram_to_image, Colormap}};
+
+        let frames: Vec<Vec<Frequency>> = (0..10)
+            .map(|i| {
+                (0..32)
+                    .map(|j| Frequency { volume: if j == i { 1.0 } else { 0.0 }, freq: j as f32, position: j as f32 / 31.0 })
+                    .collect()
+            })
+            .collect();
+
+        let image = spectrogram_to_image(&frames, 100, 64, Colormap::Grayscale);
+        assert_eq!(image.dimensions(), (100, 64));
+
+        // first frame has its loud bar at the bottom
+        assert_eq!(image.get_pixel(0, 63).0, [255; 3]);
+        assert_eq!(image.get_pixel(0, 0).0, [0; 3]);
+
+        assert_eq!(Colormap::Viridis.color(0.0), [68, 1, 84]);
+        assert_eq!(Colormap::Magma.color(1.0), [252, 253, 191]);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn spectral_flatness() {
+        use crate::spectrum::{Frequency, config::ProcessorConfig, processor::Processor};
+
+        let freqs = |volume: &dyn Fn(usize) -> f32| -> Vec<Frequency> {
+            (0..64).map(|i| Frequency { volume: volume(i), freq: i as f32 * 100.0, position: i as f32 / 63.0 }).collect()
+        };
+
+        let flat = Processor::from_frequencies(ProcessorConfig::default(), freqs(&|_| 0.5));
+        assert!((flat.spectral_flatness() - 1.0).abs() < 1e-3);
+
+        let spike = Processor::from_frequencies(ProcessorConfig::default(), freqs(&|i| if i == 20 { 1.0 } else { 0.0 }));
+        assert!(spike.spectral_flatness() < 0.01);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_lock_positions() {
+        use crate::spectrum::{config::{StreamConfig, ProcessorConfig, Interpolation}, stream::Stream};
+
+        // without interpolation positions depend on the bins, which move when `fft_resolution` changes,
+        // 448 and 450 result in the same number of bars within bounds
+        let positions = |lock_positions: bool| -> Vec<Vec<f32>> {
+            let config = StreamConfig {
+                channel_count: 1,
+                lock_positions,
+                fft_resolution: 448,
+                processor: ProcessorConfig { interpolation: Interpolation::None, ..Default::default() },
+                ..Default::default()
+            };
+            let mut stream = Stream::new(config);
+
+            let mut frames: Vec<Vec<f32>> = Vec::new();
+            for fft_res in [448, 450] {
+                stream.config.fft_resolution = fft_res;
+                stream.push_data(vec![0.1; fft_res + 1]);
+                stream.update();
+                frames.push(stream.get_frequencies()[0].iter().map(|f| f.position).collect());
+            }
+            frames
+        };
+
+        let unlocked = positions(false);
+        assert!(!unlocked[0].is_empty());
+        assert_eq!(unlocked[0].len(), unlocked[1].len());
+        assert_ne!(unlocked[0], unlocked[1]);
+
+        let locked = positions(true);
+        assert_eq!(locked[0], unlocked[0]);
+        assert_eq!(locked[0], locked[1]);
+    }
+
+    #[cfg(feature = "cpal")]
+    #[test]
+    fn capture_cpal_accessors() {
+        use crate::audio_capture::capture::{Capture, Device};
+
+        let mut capture = Capture::new();
+        assert!(capture.cpal_stream().is_none());
+        assert!(capture.cpal_device().is_none());
+
+        // requires audio hardware
+        if capture.init(&Device::DefaultInput).is_ok() {
+            assert!(capture.cpal_stream().is_some());
+            assert!(capture.cpal_device().is_some());
+        }
+    }
+
+    #[cfg(feature = "distributor")]
+    #[test]
+    fn distributor_granularity() {
+        use crate::distributor::Elapsed;
+
+        let mut distributor: Distributor<f32> = Distributor::new(1000.0, None);
+        distributor.set_granularity(2);
+
+        let mut popped: usize = 0;
+        let mut pushed: usize = 0;
+        for i in 0..200 {
+            if i % 3 == 0 {
+                distributor.push(&[0.0; 6], Elapsed::Millis(3));
+                pushed += 6;
+            }
+            let data = distributor.pop(Elapsed::Micros(1_700), None);
+            assert_eq!(data.len() % 2, 0);
+            popped += data.len();
+        }
+        assert!(popped > pushed / 2);
+    }
+
+    #[cfg(feature = "cpal")]
+    #[test]
+    fn capture_validate_config() {
+        use crate::audio_capture::capture::{validate_config, Error};
+
+        assert!(matches!(validate_config(0, 44_100), Err(Error::UnsupportedConfig)));
+        assert!(matches!(validate_config(2, 0), Err(Error::UnsupportedConfig)));
+        assert!(matches!(validate_config(2, u32::MAX), Err(Error::UnsupportedConfig)));
+        assert!(validate_config(2, 48_000).is_ok());
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_frequency_delta() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+
+        let config = StreamConfig { channel_count: 1, gravity: None, ..Default::default() };
+        let fft_res = config.fft_resolution;
+        let mut stream = Stream::new(config);
+
+        let max = |freqs: &[crate::spectrum::Frequency]| freqs.iter().map(|f| f.volume.abs()).fold(0.0, f32::max);
+        let tone = |amplitude: f32| -> Vec<f32> {
+            sine(1000.0, 44_100.0, fft_res + 1).into_iter().map(|x| amplitude * x).collect()
+        };
+
+        // first frame is its own delta
+        stream.push_data(tone(1.0));
+        stream.update();
+        let delta = stream.get_frequency_delta();
+        let frame = stream.get_frequencies();
+        assert_eq!(max(&delta[0]), max(&frame[0]));
+
+        // steady signal
+        stream.push_data(tone(1.0));
+        stream.update();
+        let steady = max(&stream.get_frequency_delta()[0]);
+        assert!(steady < 0.01);
+
+        // step
+        stream.push_data(tone(4.0));
+        stream.update();
+        let step = stream.get_frequency_delta();
+        assert!(max(&step[0]) > 0.1);
+        assert!(step[0].iter().any(|f| f.volume > 0.1));
+
+        // step down to silence reports falling energy
+        stream.push_data(vec![0.0; fft_res + 1]);
+        stream.update();
+        let fall = stream.get_frequency_delta();
+        assert!(fall[0].iter().any(|f| f.volume < -0.1));
+        assert!(fall[0].iter().all(|f| f.volume <= f32::EPSILON));
+    }
+
+    #[cfg(feature = "processor")]
+    #[test]
+    fn denormals() {
+        use crate::processor::{Processor, Plugin, Lowpass, Biquad, Denormals};
+
+        // decaying tail of a biquad ends up subnormal
+        let mut data: Vec<f32> = vec![0.0; 4096];
+        data[0] = 1.0;
+        let mut processor = Processor {
+            data: data.clone(),
+            sampling_rate: 44_100.0,
+            plugins: vec![Plugin::Biquad(Biquad::new(1000.0, 0.707)), Plugin::Denormals(Denormals::FlushToZero)],
+        };
+        processor.process();
+        assert!(processor.data.iter().all(|x| !x.is_subnormal()));
+
+        // silence through fft filter with dither
+        let mut processor = Processor {
+            data: vec![f32::MIN_POSITIVE / 2.0; 4096],
+            sampling_rate: 44_100.0,
+            plugins: vec![
+                Plugin::Denormals(Denormals::Dither(1e-20)),
+                Plugin::Lowpass(Lowpass::new(1000.0, 2000.0)),
+                Plugin::Denormals(Denormals::FlushToZero),
+            ],
+        };
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            processor.process();
+        }
+        assert!(start.elapsed().as_secs() < 5);
+        assert!(processor.data.iter().all(|x| x.is_finite() && !x.is_subnormal()));
+        assert!(processor.data.iter().all(|x| x.abs() < 1e-10));
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn last_interpolation_info() {
+        use crate::spectrum::{config::{ProcessorConfig, Interpolation}, processor::Processor};
+
+        let buffer: Vec<f32> = sine(1000.0, 44_100.0, 2048);
+
+        let modes = [
+            Interpolation::None,
+            Interpolation::Step,
+            Interpolation::Cubic,
+            Interpolation::Linear,
+            Interpolation::Gaps,
+        ];
+        for mode in modes.iter() {
+            let config = ProcessorConfig { interpolation: mode.clone(), resolution: Some(200), ..Default::default() };
+            let mut processor = Processor::from_raw_data(config, buffer.clone());
+            assert!(processor.last_interpolation_info().is_none());
+            processor.compute_all();
+
+            let info = processor.last_interpolation_info().unwrap();
+            assert_eq!(&info.mode, mode);
+            assert_eq!(info.output_len, processor.freq_buffer.len());
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_resolution_change() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+
+        let config = StreamConfig { channel_count: 1, ..Default::default() };
+        let fft_res = config.fft_resolution;
+        let mut stream = Stream::new(config);
+
+        let max = |freqs: &[crate::spectrum::Frequency]| freqs.iter().map(|f| f.volume).fold(0.0, f32::max);
+
+        let data: Vec<f32> = sine(1000.0, 44_100.0, fft_res + 1);
+        stream.push_data(data);
+        stream.update();
+        let before = max(&stream.get_frequencies()[0]);
+        assert!(before > 0.1);
+
+        // doubles number of frequencies, silence would leave display blank without resampling
+        stream.config.fft_resolution *= 2;
+        stream.push_data(vec![0.0; fft_res * 2 + 1]);
+        stream.update();
+        let after = max(&stream.get_frequencies()[0]);
+        assert!(after > before * 0.5);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_complex_spectrum() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+
+        let data: Vec<f32> = sine(1000.0, 44_100.0, 2049);
+
+        let mut stream = Stream::new(StreamConfig { channel_count: 1, ..Default::default() });
+        stream.push_data(data.clone());
+        stream.update();
+        assert!(stream.get_complex_spectrum().is_none());
+
+        let mut stream = Stream::new(StreamConfig { channel_count: 1, retain_complex: true, ..Default::default() });
+        assert!(stream.get_complex_spectrum().is_none());
+        stream.push_data(data);
+        stream.update();
+        let spectrum = stream.get_complex_spectrum().unwrap();
+        assert_eq!(spectrum.len(), 1);
+        assert_eq!(spectrum[0].len(), 2048);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn frequency_axis() {
+        use crate::spectrum::{Frequency, config::{ProcessorConfig, FrequencyAxis}, processor::Processor};
+
+        // linear sweep in steps of 100hz
+        let sweep: Vec<Frequency> = (1..=200)
+            .map(|i| Frequency { volume: 1.0, freq: i as f32 * 100.0, position: i as f32 / 200.0 })
+            .collect();
+        let positions = |axis: FrequencyAxis| -> Vec<f32> {
+            let config = ProcessorConfig { frequency_axis: axis, ..Default::default() };
+            let mut processor = Processor::from_frequencies(config, sweep.clone());
+            processor.normalize_frequency_position();
+            processor.freq_buffer.iter().map(|f| f.position).collect()
+        };
+
+        for axis in [FrequencyAxis::Linear, FrequencyAxis::Logarithmic, FrequencyAxis::Mel, FrequencyAxis::Bark].iter() {
+            let p = positions(axis.clone());
+            assert!(p.windows(2).all(|w| w[1] > w[0]), "{:?} not monotonic", axis);
+            assert!((p[199] - 1.0).abs() < 1e-4);
+        }
+
+        // equal distance in hz
+        let p = positions(FrequencyAxis::Linear);
+        assert!(((p[1] - p[0]) - (p[199] - p[198])).abs() < 1e-4);
+
+        // every octave has the same width, 100hz -> 200hz -> 400hz
+        let p = positions(FrequencyAxis::Logarithmic);
+        assert!(p[0].abs() < 1e-4);
+        assert!(((p[1] - p[0]) - (p[3] - p[1])).abs() < 1e-4);
+
+        // perceptual scales compress high frequencies
+        for axis in [FrequencyAxis::Mel, FrequencyAxis::Bark].iter() {
+            let p = positions(axis.clone());
+            assert!(p[1] - p[0] > p[199] - p[198]);
+            let expected = (axis.warp(1000.0) - axis.warp(100.0)) / (axis.warp(20_000.0) - axis.warp(100.0));
+            assert!((p[9] as f64 - expected).abs() < 1e-4);
+        }
+
+        // backward compatibility
+        #[allow(deprecated)]
+        let legacy = crate::spectrum::config::PositionNormalisation::Exponential;
+        assert_eq!(legacy, FrequencyAxis::Exponential);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn frequency_hue() {
+        use crate::spectrum::Frequency;
+
+        let freq = |freq: f32| Frequency { volume: 1.0, freq, position: 0.0 };
+
+        assert_eq!(freq(20.0).hue(20.0, 20_000.0), 0.0);
+        assert!((freq(20_000.0).hue(20.0, 20_000.0) - 360.0).abs() < 1e-3);
+
+        // log scale, 200hz is one third of the way from 20hz to 20khz
+        assert!((freq(200.0).hue(20.0, 20_000.0) - 120.0).abs() < 1e-3);
+
+        // clamped
+        assert_eq!(freq(1.0).hue(20.0, 20_000.0), 0.0);
+        assert!((freq(100_000.0).hue(20.0, 20_000.0) - 360.0).abs() < 1e-3);
+
+        assert_eq!(freq(20.0).rgb(20.0, 20_000.0), [255, 0, 0]);
+        assert_eq!(freq(200.0).rgb(20.0, 20_000.0), [0, 255, 0]);
+        assert_eq!(freq(20_000.0).rgb(20.0, 20_000.0), [255, 0, 0]);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_cached_frequencies() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+
+        let config = StreamConfig { channel_count: 1, ..Default::default() };
+        let fft_res = config.fft_resolution;
+        let mut stream = Stream::new(config);
+        assert!(stream.get_frequencies().is_empty());
+
+        let data: Vec<f32> = sine(1000.0, 44_100.0, fft_res + 1);
+        stream.push_data(data.clone());
+        stream.update();
+        let first = stream.get_frequencies();
+
+        // neither pushed data nor changed config is processed before next update
+        stream.push_data(data.iter().map(|x| x * 0.1).collect());
+        stream.config.processor.resolution = Some(10);
+        let second = stream.get_frequencies();
+        assert_eq!(first[0].len(), second[0].len());
+        assert!(first[0].iter().zip(second[0].iter()).all(|(a, b)| a.volume == b.volume));
+
+        stream.update();
+        assert_eq!(stream.get_frequencies()[0].len(), 10);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn manual_distribution() {
+        use crate::spectrum::{config::{ProcessorConfig, Interpolation}, processor::Processor};
+
+        let buffer: Vec<f32> = sine(1000.0, 44_100.0, 2048);
+        let positions = |distribution: Option<Vec<(usize, f32)>>| -> Vec<f32> {
+            let config = ProcessorConfig {
+                manual_position_distribution: distribution,
+                interpolation: Interpolation::None,
+                ..Default::default()
+            };
+            let mut processor = Processor::from_raw_data(config, buffer.clone());
+            processor.compute_all();
+            processor.freq_buffer.iter().map(|f| f.position).collect()
+        };
+
+        let default = positions(None);
+        let distributed = positions(Some(vec![(0, 1.0), (1000, 4.0), (20_000, 1.0)]));
+        assert!(!default.is_empty());
+
+        // gets ignored without feature
+        #[cfg(feature = "manual_distribution")]
+        assert_ne!(default, distributed);
+        #[cfg(not(feature = "manual_distribution"))]
+        assert_eq!(default, distributed);
+    }
+
+    // minimal executor for async tests, parks thread until woken
+    #[cfg(feature = "async")]
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake};
+        use std::thread::{self, Thread};
+
+        struct ThreadWaker(Thread);
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[cfg(all(feature = "async", feature = "spectrum"))]
+    #[test]
+    fn async_stream() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream, async_stream::AsyncStream};
+
+        let stream = AsyncStream::new(Stream::new(StreamConfig { channel_count: 1, ..Default::default() }));
+
+        let inner = stream.stream();
+        let feeder = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            let data: Vec<f32> = sine(1000.0, 44_100.0, 4097);
+            let mut stream = inner.lock().unwrap();
+            stream.push_data(data);
+            stream.update();
+        });
+
+        let frequencies = block_on(stream.get_frequencies());
+        assert_eq!(frequencies.len(), 1);
+        assert!(frequencies[0].iter().any(|f| f.volume > 0.0));
+        feeder.join().unwrap();
+    }
+
+    #[cfg(all(feature = "async", feature = "cpal"))]
+    #[test]
+    fn async_receiver() {
+        use crate::audio_capture::capture::{Capture, Device};
+
+        let mut capture = Capture::new();
+        // requires audio hardware
+        if capture.init(&Device::DefaultInput).is_ok() {
+            let mut samples = capture.get_receiver().unwrap().into_stream();
+            let data = block_on(samples.next());
+            assert!(data.is_some());
+        }
+    }
+
+    #[test]
+    fn note_to_freq() {
+        use crate::utils::{note_to_freq, freq_to_note, NoteError};
+
+        assert!((note_to_freq("A4").unwrap() - 440.0).abs() < 1e-3);
+        assert!((note_to_freq("C#3").unwrap() - 138.59).abs() < 0.01);
+        assert_eq!(note_to_freq("Db3"), note_to_freq("C#3"));
+        assert!((freq_to_note(note_to_freq("C-1").unwrap())).abs() < 1e-3);
+
+        assert!(matches!(note_to_freq("H2"), Err(NoteError::InvalidNote(_))));
+        assert!(matches!(note_to_freq("A"), Err(NoteError::InvalidNote(_))));
+        assert!(matches!(note_to_freq(""), Err(NoteError::InvalidNote(_))));
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn bounds_notes() {
+        use crate::spectrum::config::ProcessorConfig;
+        use crate::utils::NoteError;
+
+        let mut config = ProcessorConfig::default();
+        config.set_bounds_notes("A0", "C8").unwrap();
+        assert_eq!(config.frequency_bounds, [27, 4187]);
+
+        assert_eq!(config.set_bounds_notes("C8", "A0"), Err(NoteError::InvalidRange));
+        assert!(config.set_bounds_notes("A0", "X8").is_err());
+        assert_eq!(config.frequency_bounds, [27, 4187]);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn tempo_estimator() {
+        use crate::spectrum::tempo::TempoEstimator;
+
+        for bpm in [70.0_f32, 128.0, 174.0].iter() {
+            let mut tempo = TempoEstimator::default();
+            let interval = 60.0 / bpm;
+
+            tempo.push_onset(0.0);
+            assert!(tempo.bpm().is_none());
+
+            for i in 1..(10.0 / interval) as usize {
+                tempo.push_onset(i as f32 * interval);
+            }
+            let estimate = tempo.bpm().unwrap();
+            assert!((estimate - bpm).abs() < 1.0, "expected {} got {}", bpm, estimate);
+        }
+    }
+
+    #[test]
+    fn stereo_meters() {
+        use crate::utils::{stereo_width, balance};
+
+        let sine: Vec<f32> = (0..1024).map(|i| (i as f32 * 0.1).sin()).collect();
+        let inverted: Vec<f32> = sine.iter().map(|x| -x).collect();
+        let quiet: Vec<f32> = sine.iter().map(|x| x * 0.5).collect();
+        let silence = vec![0.0; 1024];
+
+        assert_eq!(stereo_width(&sine, &sine), 0.0);
+        assert!(stereo_width(&sine, &inverted) > 0.99);
+        assert_eq!(stereo_width(&silence, &silence), 0.0);
+
+        assert_eq!(balance(&sine, &sine), 0.0);
+        assert!(balance(&sine, &quiet) < 0.0);
+        assert!((balance(&sine, &silence) + 1.0).abs() < 1e-6);
+        assert_eq!(balance(&silence, &silence), 0.0);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn frames_to_csv() {
+        use crate::spectrum::{Frequency, export::frames_to_csv};
+
+        let frames: Vec<Vec<Frequency>> = (0..3)
+            .map(|i| {
+                (1..=4)
+                    .map(|j| Frequency { volume: (i * j) as f32 * 0.25, freq: j as f32 * 100.0, position: 0.0 })
+                    .collect()
+            })
+            .collect();
+
+        let mut csv: Vec<u8> = Vec::new();
+        frames_to_csv(&frames, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], "100,200,300,400");
+        assert_eq!(rows[3].split(',').nth(1), Some("1"));
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_max_attack() {
+        use crate::spectrum::{config::{StreamConfig, ProcessorConfig, Interpolation}, stream::Stream};
+
+        let config = StreamConfig {
+            channel_count: 1,
+            gravity: None,
+            max_attack_per_frame: Some(0.05),
+            processor: ProcessorConfig { interpolation: Interpolation::Linear, ..Default::default() },
+            ..Default::default()
+        };
+        let fft_res = config.fft_resolution;
+        let mut stream = Stream::new(config);
+
+        let max = |freqs: &[crate::spectrum::Frequency]| freqs.iter().map(|f| f.volume).fold(0.0, f32::max);
+
+        // first silent frame, then a sudden loud signal
+        let mut data = vec![0.0; fft_res + 1];
+        stream.push_data(data.clone());
+        stream.update();
+
+        data = sine(1000.0, 44_100.0, data.len()).into_iter().map(|x| 4.0 * x).collect();
+        let mut last: f32 = 0.0;
+        for _ in 0..5 {
+            stream.push_data(data.clone());
+            stream.update();
+            let volume = max(&stream.get_frequencies()[0]);
+            assert!(volume > last);
+            assert!(volume - last <= 0.05 + 1e-4);
+            last = volume;
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn stream_parallel() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+
+        let channels: usize = 8;
+        let tones: Vec<Vec<f32>> = (0..channels).map(|c| sine(200.0 * (c + 1) as f32, 44_100.0, 2049)).collect();
+        let data: Vec<f32> = (0..2049).flat_map(|i| tones.iter().map(move |tone| tone[i])).collect();
+
+        let frequencies = |threads: usize| {
+            let mut stream = Stream::new(StreamConfig {
+                channel_count: channels as u16,
+                max_threads: Some(threads),
+                ..Default::default()
+            });
+            stream.push_data(data.clone());
+            stream.update();
+            stream.get_frequencies()
+        };
+
+        let sequential = frequencies(1);
+        let parallel = frequencies(3);
+        assert_eq!(sequential.len(), channels);
+        for (s, p) in sequential.iter().zip(parallel.iter()) {
+            assert_eq!(s.len(), p.len());
+            assert!(s.iter().zip(p.iter()).all(|(s, p)| s.volume == p.volume && s.freq == p.freq));
+        }
+
+        // worker threads are kept, so plans of the first frame are reused afterwards
+        let mut stream = Stream::new(StreamConfig {
+            channel_count: channels as u16,
+            max_threads: Some(3),
+            ..Default::default()
+        });
+        stream.push_data(data.clone());
+        stream.update();
+        let planned = stream.pool_plans_created();
+        assert!(planned > 0);
+        for _ in 0..10 {
+            stream.push_data(data.clone());
+            stream.update();
+        }
+        assert_eq!(stream.pool_plans_created(), planned);
+    }
+
+    #[cfg(all(feature = "spectrum", feature = "std"))]
+    #[test]
+    fn stream_interpolated() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+
+        let config = StreamConfig { channel_count: 1, gravity: None, ..Default::default() };
+        let fft_res = config.fft_resolution;
+        let mut stream = Stream::new(config);
+
+        let tone = |amplitude: f32| -> Vec<f32> {
+            sine(1000.0, 44_100.0, fft_res + 1).into_iter().map(|x| amplitude * x).collect()
+        };
+
+        stream.push_data(tone(0.5));
+        stream.update();
+        let first = stream.get_frequencies();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        stream.push_data(tone(1.0));
+        stream.update();
+        let second = stream.get_frequencies();
+
+        let (previous, current) = stream.last_frame_times().unwrap();
+        let halfway = current + (current - previous) / 2;
+        let interpolated = stream.get_frequencies_interpolated(halfway);
+
+        for ((i, a), b) in interpolated[0].iter().zip(first[0].iter()).zip(second[0].iter()) {
+            assert!((i.volume - (a.volume + b.volume) / 2.0).abs() < 1e-4);
+        }
+
+        // one interval after last frame it is reached
+        let end = stream.get_frequencies_interpolated(current + (current - previous));
+        assert!(end[0].iter().zip(second[0].iter()).all(|(e, s)| (e.volume - s.volume).abs() < 1e-6));
+    }
+
+    #[cfg(all(feature = "cpal", feature = "distributor", feature = "spectrum"))]
+    #[test]
+    fn pipeline_refresh_interval() {
+        use crate::pipeline::{refresh_interval, MIN_REFRESH_INTERVAL};
+        use std::time::Duration;
+
+        assert_eq!(refresh_interval(50), Duration::from_millis(20));
+        assert_eq!(refresh_interval(0), Duration::from_secs(1));
+
+        // updates per second stay bounded no matter how high refresh rate is
+        for refresh_rate in [1_000, 100_000, usize::MAX] {
+            let interval = refresh_interval(refresh_rate);
+            assert!(interval >= MIN_REFRESH_INTERVAL);
+            assert!(1.0 / interval.as_secs_f64() <= 1000.0 + 1e-6);
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn amplitude_mode() {
+        use crate::spectrum::{config::{ProcessorConfig, AmplitudeMode}, processor::Processor};
+
+        let data: Vec<f32> = (0..1024).map(|i| (i as f32 * 0.2).sin() + (i as f32 * 0.05).cos()).collect();
+        let spectrum = |mode: AmplitudeMode| -> Vec<f32> {
+            let config = ProcessorConfig { amplitude_mode: mode, ..Default::default() };
+            let mut processor = Processor::from_raw_data(config, data.clone());
+            processor.apodize();
+            processor.fft();
+            processor.raw_buffer
+        };
+
+        let magnitude = spectrum(AmplitudeMode::Magnitude);
+        let power = spectrum(AmplitudeMode::Power);
+        let sqrt = spectrum(AmplitudeMode::Sqrt);
+        for ((m, p), s) in magnitude.iter().zip(power.iter()).zip(sqrt.iter()) {
+            assert!((m * m - p).abs() <= 1e-4 * p.max(1.0));
+            assert!((s * s - m).abs() <= 1e-4 * m.max(1.0));
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn indexed_frequencies() {
+        use crate::spectrum::{config::{ProcessorConfig, Interpolation, VolumeNormalisation}, processor::Processor};
+
+        let data: Vec<f32> = sine(2000.0, 44_100.0, 4096);
+        let mut raw = Processor::from_raw_data(ProcessorConfig::default(), data.clone());
+        raw.apodize();
+        raw.fft();
+        let peak_bin = raw.raw_buffer
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .unwrap()
+            .0;
+
+        for (interpolation, tolerance) in [(Interpolation::None, 0), (Interpolation::Linear, 1)] {
+            let config = ProcessorConfig {
+                interpolation,
+                volume_normalisation: VolumeNormalisation::None,
+                resolution: Some(400),
+                ..Default::default()
+            };
+            let mut processor = Processor::from_raw_data(config, data.clone());
+            processor.compute_all();
+
+            let (index, _) = processor.indexed_frequencies()
+                .into_iter()
+                .max_by(|a, b| a.1.volume.partial_cmp(&b.1.volume).unwrap())
+                .unwrap();
+            assert!((index as i64 - peak_bin as i64).abs() <= tolerance, "{} != {}", index, peak_bin);
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn pinned_frequencies() {
+        use crate::spectrum::{config::{ProcessorConfig, Interpolation, FrequencyAxis}, processor::Processor};
+
+        let data: Vec<f32> = (0..4096).map(|i| (i as f32 * 0.2).sin()).collect();
+        let pinned = vec![100.0, 1000.0, 10_000.0];
+        for interpolation in [Interpolation::None, Interpolation::Step, Interpolation::Cubic] {
+            let config = ProcessorConfig {
+                frequency_axis: FrequencyAxis::Logarithmic,
+                interpolation,
+                resolution: Some(64),
+                pinned_frequencies: pinned.clone(),
+                ..Default::default()
+            };
+            let mut unpinned = Processor::from_raw_data(ProcessorConfig { pinned_frequencies: Vec::new(), ..config.clone() }, data.clone());
+            unpinned.compute_all();
+            let mut processor = Processor::from_raw_data(config, data.clone());
+            processor.compute_all();
+
+            for hz in pinned.iter() {
+                let bar = processor.freq_buffer.iter().find(|f| f.freq == *hz);
+                assert!(bar.is_some(), "no bar at {}hz", hz);
+
+                // pinned bar sits between the regular bars that surround its frequency
+                let bars = &unpinned.freq_buffer;
+                if let Some(i) = bars.windows(2).position(|w| w[0].freq < *hz && *hz < w[1].freq) {
+                    let t = (hz - bars[i].freq) / (bars[i + 1].freq - bars[i].freq);
+                    let expected = bars[i].position + (bars[i + 1].position - bars[i].position) * t;
+                    let step = bars[i + 1].position - bars[i].position;
+                    assert!((bar.unwrap().position - expected).abs() < step * 0.25, "bar of {}hz is misplaced", hz);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn subtract_noise_floor() {
+        use crate::spectrum::{config::ProcessorConfig, processor::Processor, Frequency};
+
+        // noise between 0.1 and 0.2 with two peaks
+        let freqs: Vec<Frequency> = (0..100)
+            .map(|i| {
+                let volume = match i {
+                    20 => 5.0,
+                    70 => 3.0,
+                    _ => 0.1 + (i % 10) as f32 * 0.01,
+                };
+                Frequency { volume, ..Frequency::empty() }
+            })
+            .collect();
+
+        let mut processor = Processor::from_frequencies(ProcessorConfig::default(), freqs);
+        let floor = processor.subtract_noise_floor(0.5);
+        assert!(floor > 0.1 && floor < 0.2);
+
+        let zeroed = processor.freq_buffer.iter().filter(|f| f.volume == 0.0).count();
+        assert!(zeroed >= 50);
+        assert!((processor.freq_buffer[20].volume - (5.0 - floor)).abs() < 1e-6);
+        assert!(processor.freq_buffer[70].volume > 2.5);
+        assert!(processor.freq_buffer.iter().all(|f| f.volume >= 0.0 && f.volume < 0.2 || f.volume > 2.5));
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn cubic_tension() {
+        use crate::spectrum::{config::{ProcessorConfig, Interpolation}, processor::Processor, Frequency};
+
+        // sharp plateau, which cubic interpolation overshoots
+        let volumes = [0.0, 0.0, 1.0, 1.0, 0.0, 0.0];
+        let freqs: Vec<Frequency> = volumes
+            .iter()
+            .enumerate()
+            .map(|(i, v)| Frequency { volume: *v, freq: (i + 1) as f32 * 100.0, position: i as f32 / volumes.len() as f32 })
+            .collect();
+
+        let max_volume = |tension: f32| -> f32 {
+            let config = ProcessorConfig {
+                interpolation: Interpolation::Cubic,
+                resolution: Some(120),
+                cubic_tension: tension,
+                ..Default::default()
+            };
+            let mut processor = Processor::from_frequencies(config, freqs.clone());
+            processor.interpolate();
+            processor.freq_buffer.iter().map(|f| f.volume).fold(f32::MIN, f32::max)
+        };
+
+        let default = max_volume(0.0);
+        let tense = max_volume(0.5);
+        assert!(default > 1.1);
+        assert!(tense < default && tense > 1.0);
+        assert!((max_volume(1.0) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn utils_rms_peak() {
+        use crate::utils::{peak, rms};
+
+        let square: Vec<f32> = (0..64).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        assert!((rms(&square) - 1.0).abs() < 1e-6);
+        assert_eq!(peak(&square), 1.0);
+
+        assert_eq!(rms(&[]), 0.0);
+        assert_eq!(peak(&[]), 0.0);
+        assert_eq!(peak(&[0.5, -2.0, 1.0]), 2.0);
+    }
+
+    #[test]
+    fn downmix_to_mono() {
+        use crate::utils::downmix_to_mono;
+
+        assert_eq!(downmix_to_mono(&[1.0, 0.0, 0.0, 1.0], 2), vec![0.5, 0.5]);
+        assert_eq!(downmix_to_mono(&[1.0, 0.0, 0.0, 1.0], 1), vec![1.0, 0.0, 0.0, 1.0]);
+        assert!(downmix_to_mono(&[1.0, 0.0], 0).is_empty());
+    }
+
+    #[test]
+    fn interleave_channels() {
+        use crate::utils::{interleave_channels, seperate_channels};
+
+        let data: Vec<f32> = (0..12).map(|i| i as f32).collect();
+        for n in [1, 2, 3, 4] {
+            assert_eq!(interleave_channels(&seperate_channels(&data, n)), data);
+        }
+
+        assert_eq!(interleave_channels(&[vec![0.0, 2.0, 4.0], vec![1.0]]), vec![0.0, 1.0]);
+        assert!(interleave_channels(&[]).is_empty());
+    }
+
+    #[test]
+    fn planar_interleaved() {
+        use crate::utils::{interleaved_to_planar, planar_to_interleaved};
+
+        let left: Vec<f32> = vec![1.0, 2.0, 3.0];
+        let right: Vec<f32> = vec![-1.0, -2.0, -3.0];
+
+        let interleaved = planar_to_interleaved(&[&left, &right]).unwrap();
+        assert_eq!(interleaved, vec![1.0, -1.0, 2.0, -2.0, 3.0, -3.0]);
+        assert_eq!(interleaved_to_planar(&interleaved, 2), vec![left.clone(), right]);
+
+        assert_eq!(planar_to_interleaved(&[&left, &[0.0]]), None);
+        assert_eq!(planar_to_interleaved(&[]), Some(vec![]));
+    }
+
+    #[test]
+    fn block_accumulator() {
+        use crate::utils::BlockAccumulator;
+
+        let data: Vec<f32> = (0..1000).map(|i| i as f32).collect();
+        let chunk_sizes = [1, 7, 100, 3, 250, 64, 0, 575];
+        assert_eq!(chunk_sizes.iter().sum::<usize>(), data.len());
+
+        for (block_size, hop_size) in [(128, 64), (128, 128), (100, 150)] {
+            let mut accumulator = BlockAccumulator::new(block_size, hop_size);
+            let mut frames: Vec<Vec<f32>> = Vec::new();
+            let mut offset = 0;
+            for size in chunk_sizes.iter() {
+                frames.append(&mut accumulator.push(&data[offset..offset + size]));
+                offset += size;
+            }
+
+            let expected: Vec<Vec<f32>> = crate::utils::frame(&data, block_size, hop_size, crate::utils::FrameTail::Drop)
+                .iter()
+                .map(|f| f.to_vec())
+                .collect();
+            assert_eq!(frames, expected);
+            for (i, frame) in frames.iter().enumerate() {
+                assert_eq!(frame.len(), block_size);
+                assert_eq!(frame[0], (i * hop_size) as f32);
+            }
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn interpolated_freq_matches_position() {
+        use crate::spectrum::{config::{ProcessorConfig, Interpolation, FrequencyAxis}, processor::Processor};
+
+        let data: Vec<f32> = (0..8192).map(|i| (i as f32 * 0.1).sin()).collect();
+        for axis in [FrequencyAxis::Linear, FrequencyAxis::Logarithmic] {
+            let bounded = |interpolation: Interpolation| -> Vec<crate::spectrum::Frequency> {
+                let config = ProcessorConfig {
+                    frequency_axis: axis.clone(),
+                    interpolation,
+                    resolution: Some(100),
+                    ..Default::default()
+                };
+                let mut processor = Processor::from_raw_data(config, data.clone());
+                processor.compute_all();
+                processor.freq_buffer
+            };
+            let raw = bounded(Interpolation::None);
+            let (low, high) = (raw[0].freq, raw[raw.len() - 1].freq);
+            let mid = match axis {
+                FrequencyAxis::Linear => (low + high) / 2.0,
+                _ => (low * high).sqrt(),
+            };
+
+            for interpolation in [Interpolation::Step, Interpolation::Gaps, Interpolation::Linear, Interpolation::Cubic] {
+                let bars = bounded(interpolation.clone());
+                assert_eq!(bars[50].position, 0.5);
+                assert!((bars[50].freq - mid).abs() / mid < 0.02, "{:?} {:?}: {} != {}", axis, interpolation, bars[50].freq, mid);
+                assert!(bars.windows(2).all(|w| w[0].freq <= w[1].freq));
+            }
+        }
+    }
+
+    #[cfg(all(feature = "spectrum", feature = "std"))]
+    #[test]
+    fn compute_all_timed() {
+        use crate::spectrum::{processor::Processor, config::ProcessorConfig};
+
+        let data: Vec<f32> = (0..4096).map(|i| (i as f32 * 0.1).sin()).collect();
+
+        let mut timed = Processor::from_raw_data(ProcessorConfig::default(), data.clone());
+        let timings = timed.compute_all_timed();
+        let sum = timings.apodize + timings.fft + timings.normalize + timings.interpolate;
+        assert!(sum <= timings.total);
+        assert!(timings.total - sum < std::time::Duration::from_millis(10));
+
+        // same output as untimed pipeline
+        let mut untimed = Processor::from_raw_data(ProcessorConfig::default(), data);
+        untimed.compute_all();
+        assert_eq!(timed.freq_buffer.len(), untimed.freq_buffer.len());
+        assert!(timed.freq_buffer.iter().zip(untimed.freq_buffer.iter()).all(|(a, b)| a.volume == b.volume));
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn calibrate() {
+        use crate::spectrum::{processor::Processor, config::{ProcessorConfig, Interpolation, VolumeNormalisation}};
+
+        // 1000hz lies exactly on a bin for all lengths
+        let tone = |amplitude: f32, len: usize| -> Vec<f32> {
+            sine(1000.0, 32_000.0, len).into_iter().map(|x| amplitude * x).collect()
+        };
+        let config = ProcessorConfig {
+            sampling_rate: 32_000,
+            volume_normalisation: VolumeNormalisation::None,
+            interpolation: Interpolation::None,
+            ..Default::default()
+        };
+
+        let mut reference = Processor::from_raw_data(config, tone(0.25, 4096));
+        assert!(reference.calibrate(0.25).is_some());
+        let calibrated = reference.config().clone();
+
+        for len in [1024, 4096, 16384] {
+            let mut processor = Processor::from_raw_data(calibrated.clone(), tone(1.0, len));
+            processor.compute_all();
+            let peak = processor.freq_buffer.iter().fold(0.0_f32, |max, f| max.max(f.volume));
+            assert!((peak - 1.0).abs() < 0.01, "{}: {}", len, peak);
+        }
+
+        assert!(Processor::from_raw_data(ProcessorConfig::default(), vec![0.0; 1024]).calibrate(1.0).is_none());
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn position_gamma() {
+        use crate::spectrum::{processor::Processor, config::{ProcessorConfig, FrequencyAxis, Interpolation, MIN_GAMMA}};
+
+        let data: Vec<f32> = (0..1024).map(|i| (i as f32 * 0.3).sin()).collect();
+        let positions = |frequency_axis: FrequencyAxis| -> Vec<f32> {
+            let config = ProcessorConfig { frequency_axis, interpolation: Interpolation::None, ..Default::default() };
+            let mut processor = Processor::from_raw_data(config, data.clone());
+            processor.compute_all();
+            processor.freq_buffer.iter().map(|f| f.position).collect()
+        };
+
+        let exponential = positions(FrequencyAxis::Exponential);
+        let gamma = positions(FrequencyAxis::Gamma(0.5));
+        assert_eq!(exponential.len(), gamma.len());
+        assert!(exponential.iter().zip(gamma.iter()).all(|(a, b)| (a - b).abs() < 1e-6));
+
+        let linear = positions(FrequencyAxis::Linear);
+        assert!(linear.iter().zip(positions(FrequencyAxis::Gamma(1.0)).iter()).all(|(a, b)| (a - b).abs() < 1e-6));
+
+        // gamma is clamped to a positive value
+        let clamped = positions(FrequencyAxis::Gamma(MIN_GAMMA));
+        for gamma in [0.0, -1.0] {
+            let positions = positions(FrequencyAxis::Gamma(gamma));
+            assert!(positions.iter().all(|p| p.is_finite()));
+            assert_eq!(positions, clamped);
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn frequency_uncertainty() {
+        use crate::spectrum::{processor::Processor, config::ProcessorConfig};
+
+        let config = ProcessorConfig { resolution: Some(256), ..Default::default() };
+        let mut processor = Processor::from_raw_data(config, vec![0.0_f32; 2048]);
+        processor.compute_all();
+
+        let freqs = &processor.freq_buffer;
+        let relative = |i: usize| processor.frequency_uncertainty(i) / (freqs[i + 1].freq - freqs[i].freq);
+        let last = freqs.len() - 2;
+
+        assert!(processor.frequency_uncertainty(last) > processor.frequency_uncertainty(1));
+        assert!(relative(1) > relative(last));
+        assert!(processor.frequency_uncertainty(freqs.len()).is_nan());
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn restrict_to_bounds() {
+        use crate::spectrum::{processor::Processor, config::{ProcessorConfig, Interpolation, FrequencyAxis}};
+
+        let data: Vec<f32> = (0..4096)
+            .map(|i| {
+                let t = i as f32 / 44_100.0 * 2.0 * std::f32::consts::PI;
+                (t * 440.0).sin() + 0.5 * (t * 1500.0).sin() + 0.3 * (t * 5000.0).sin()
+            })
+            .collect();
+
+        for (interpolation, frequency_axis) in [
+            (Interpolation::None, FrequencyAxis::Harmonic),
+            (Interpolation::Cubic, FrequencyAxis::Harmonic),
+            (Interpolation::Linear, FrequencyAxis::Exponential),
+        ] {
+            let full_config = ProcessorConfig {
+                frequency_bounds: [200, 2000],
+                resolution: Some(128),
+                interpolation,
+                frequency_axis,
+                ..Default::default()
+            };
+            let restricted_config = ProcessorConfig { restrict_to_bounds: true, ..full_config.clone() };
+
+            let mut full = Processor::from_raw_data(full_config, data.clone());
+            full.compute_all();
+            let mut restricted = Processor::from_raw_data(restricted_config, data.clone());
+            restricted.compute_all();
+
+            assert_eq!(full.freq_buffer.len(), restricted.freq_buffer.len());
+            for (a, b) in full.freq_buffer.iter().zip(restricted.freq_buffer.iter()) {
+                assert!((a.freq - b.freq).abs() < 1e-2, "{} != {}", a.freq, b.freq);
+                assert!((a.volume - b.volume).abs() < 1e-4, "{} != {}", a.volume, b.volume);
+                assert!((a.position - b.position).abs() < 1e-4, "{} != {}", a.position, b.position);
+            }
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn spectrogram_round_trip() {
+        use crate::spectrum::{Frequency, Spectrogram};
+
+        let frames: Vec<Vec<Frequency>> = (0..3)
+            .map(|frame| {
+                (0..5)
+                    .map(|bin| Frequency { volume: (frame * 5 + bin) as f32 * 0.1, freq: bin as f32 * 100.0, position: 0.0 })
+                    .collect()
+            })
+            .collect();
+        let spectrogram = Spectrogram::from_frames(&frames, 48_000);
+
+        let path = std::env::temp_dir().join(format!("audioviz_spectrogram_{}.bin", std::process::id()));
+        spectrogram.save(&path).unwrap();
+        let loaded = Spectrogram::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, spectrogram);
+        assert_eq!(loaded.frames[2][4], 1.4);
+        assert_eq!(loaded.frequencies[1], 100.0);
+
+        let mut bytes: Vec<u8> = Vec::new();
+        spectrogram.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 20 + 4 * 5 + 4 * 15);
+        assert!(Spectrogram::read_from(&bytes[..bytes.len() - 1]).is_err());
+        bytes[0] = b'X';
+        assert_eq!(Spectrogram::read_from(&bytes[..]).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn analyze_overlap() {
+        use crate::spectrum::{analyze, config::{ProcessorConfig, AnalyzeConfig, Interpolation, Window}};
+
+        let data: Vec<f32> = sine(1000.0, 44_100.0, 44_100);
+
+        let peaks = |window: &Window, overlap: f32, overlap_add: bool| -> Vec<f32> {
+            let config = ProcessorConfig { interpolation: Interpolation::None, window: window.clone(), ..Default::default() };
+            let analyze_config = AnalyzeConfig { window_size: 1024, overlap, overlap_add, ..Default::default() };
+            analyze(&data, &config, &analyze_config)
+                .iter()
+                .map(|(_, freqs)| freqs.iter().map(|f| f.volume).fold(0.0, f32::max))
+                .collect()
+        };
+        let sum = |peaks: Vec<f32>| -> f32 { peaks.iter().sum() };
+        let close = |a: f32, b: f32| (a - b).abs() / b < 0.05;
+
+        for window in [Window::Hann, Window::Blackman] {
+            // single windows do not depend on overlap
+            let reference = peaks(&window, 0.0, false);
+            assert_eq!(reference.len(), 44_100 / 1024);
+            let reference_sum = sum(reference.clone());
+            let reference = reference_sum / reference.len() as f32;
+            for overlap in [0.5, 0.75] {
+                let overlapped = peaks(&window, overlap, false);
+                assert!(overlapped.len() > 44_100 / 1024);
+                assert!(sum(overlapped.clone()) > reference_sum * 1.9);
+                for peak in overlapped {
+                    assert!(close(peak, reference), "{:?} {}: {} != {}", window, overlap, peak, reference);
+                }
+            }
+
+            // overlap-added sums do not depend on overlap
+            let corrected = sum(peaks(&window, 0.0, true));
+            for overlap in [0.5, 0.75] {
+                let overlapped = sum(peaks(&window, overlap, true));
+                assert!(close(overlapped, corrected), "{:?} {}: {} != {}", window, overlap, overlapped, corrected);
+            }
+        }
+
+        // hann windows at 50% overlap add up to 1.0, so volumes barely change
+        let hann = peaks(&Window::Hann, 0.5, false);
+        for (corrected, peak) in peaks(&Window::Hann, 0.5, true).iter().zip(hann.iter()) {
+            assert!(close(*corrected, *peak));
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn volume_scale_decibel() {
+        use crate::spectrum::{config::{ProcessorConfig, VolumeNormalisation, VolumeScale, Window}, processor::Processor};
+
+        for (amplitude, expected_db) in [(1.0, 0.0), (0.5, -6.02), (0.01, -40.0)] {
+            for window in [Window::Hann, Window::Rectangular, Window::Blackman] {
+                // sine exactly on bin 64
+                let data: Vec<f32> = sine(64.0, 2048.0, 2048).into_iter().map(|x| amplitude * x).collect();
+                let config = ProcessorConfig {
+                    window: window.clone(),
+                    volume_normalisation: VolumeNormalisation::None,
+                    volume_scale: VolumeScale::Decibel { floor_db: -90.0 },
+                    ..Default::default()
+                };
+                let mut processor = Processor::from_raw_data(config, data);
+                processor.apodize();
+                processor.fft();
+                processor.raw_to_freq_buffer();
+
+                let peak = processor.freq_buffer.iter().map(|f| f.volume).fold(0.0, f32::max);
+                let db = peak * 90.0 - 90.0;
+                assert!((db - expected_db).abs() < 0.1, "{:?}: {} != {}", window, db, expected_db);
+                assert!(processor.freq_buffer.iter().all(|f| f.volume >= 0.0 && f.volume <= 1.0 + 1e-3));
+            }
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn raw_bins() {
+        use crate::spectrum::{config::ProcessorConfig, processor::Processor};
+
+        let data: Vec<f32> = sine(100.0, 2048.0, 2048);
+        let mut processor = Processor::from_raw_data(ProcessorConfig::default(), data);
+        assert!(processor.raw_bins().is_empty());
+        processor.compute_all();
+
+        assert_eq!(processor.raw_bins().len(), 2048 / 2 + 1);
+        assert_eq!(processor.raw_bin(100), Some(processor.raw_bins()[100]));
+        assert_eq!(processor.raw_bin(1025), None);
+        let peak = processor.raw_bins()
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .unwrap()
+            .0;
+        assert_eq!(peak, 100);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn window_functions() {
+        use crate::spectrum::{config::{ProcessorConfig, Window}, processor::Processor};
+
+        let data: Vec<f32> = vec![1.0; 1000];
+        let windowed = |window: Window| -> Vec<f32> {
+            let mut processor = Processor::from_raw_data(ProcessorConfig { window, ..Default::default() }, data.clone());
+            processor.apodize();
+            processor.raw_buffer
+        };
+
+        assert_eq!(windowed(Window::Rectangular), data);
+
+        let hann = windowed(Window::Hann);
+        let blackman = windowed(Window::Blackman);
+        for i in [10, 50, 100, 200] {
+            assert!(blackman[i] < hann[i]);
+        }
+        // both are 1.0 in the middle
+        assert!((hann[500] - 1.0).abs() < 1e-3 && (blackman[500] - 1.0).abs() < 1e-3);
+
+        let harris = windowed(Window::BlackmanHarris);
+        let hamming = windowed(Window::Hamming);
+        assert!(harris[50] < blackman[50]);
+        assert!((hamming[0] - 0.08).abs() < 1e-4);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn per_channel_processor() {
+        use crate::spectrum::{config::{StreamConfig, ProcessorConfig, Interpolation}, stream::Stream};
+
+        let config = |bounds: [usize; 2]| ProcessorConfig {
+            frequency_bounds: bounds,
+            interpolation: Interpolation::None,
+            ..Default::default()
+        };
+        let mut stream = Stream::new(StreamConfig {
+            channel_count: 3,
+            fft_resolution: 1024,
+            per_channel_processor: Some(vec![config([50, 1000]), config([5000, 15_000])]),
+            ..Default::default()
+        });
+        let data: Vec<f32> = (0..2048 * 3).map(|i| (i as f32 * 0.1).sin()).collect();
+        stream.push_data(data);
+        stream.update();
+
+        let frequencies = stream.get_frequencies();
+        let range = |channel: usize| {
+            let freqs = &frequencies[channel];
+            (freqs.first().unwrap().freq, freqs.last().unwrap().freq)
+        };
+        let (low, high) = range(0);
+        assert!(low >= 50.0 && high <= 1000.0);
+        let (low, high) = range(1);
+        assert!(low >= 5000.0 && high <= 15_000.0);
+        // falls back to shared config
+        let (low, high) = range(2);
+        assert!(low < 1000.0 && high > 15_000.0);
+    }
+
+    #[cfg(all(feature = "spectrum", feature = "std"))]
+    #[test]
+    fn stream_config_transition() {
+        use crate::spectrum::{config::{StreamConfig, ProcessorConfig, Interpolation}, stream::Stream};
+        use std::time::Duration;
+
+        let data: Vec<f32> = (0..2048).map(|i| (i as f32 * 0.3).sin()).collect();
+        let volume = |stream: &mut Stream| -> f32 {
+            stream.get_frequencies().iter().flatten().map(|f| f.volume).sum()
+        };
+        let mut stream = Stream::new(StreamConfig {
+            channel_count: 1,
+            fft_resolution: 1024,
+            gravity: None,
+            config_transition: Some(Duration::from_secs(2)),
+            processor: ProcessorConfig { interpolation: Interpolation::None, ..Default::default() },
+            ..Default::default()
+        });
+        stream.push_data(data.clone());
+        stream.update();
+        let old = volume(&mut stream);
+
+        stream.config.processor.volume = 2.0;
+        stream.push_data(data.clone());
+        stream.update();
+        std::thread::sleep(Duration::from_millis(400));
+        stream.push_data(data.clone());
+        stream.update();
+        let blended = volume(&mut stream);
+
+        // volume doubles, so blend is somewhere between both outputs
+        let t = (blended - old) / old;
+        assert!(t > 0.1 && t < 0.9, "{}", t);
+
+        // differing lengths get resampled
+        stream.config.processor.resolution = Some(50);
+        stream.config.processor.interpolation = Interpolation::Linear;
+        stream.push_data(data.clone());
+        stream.update();
+        assert_eq!(stream.get_frequencies()[0].len(), 50);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_underrun() {
+        use crate::spectrum::{config::{StreamConfig, UnderrunBehavior, ProcessorConfig, Interpolation}, stream::Stream};
+
+        let data: Vec<f32> = (0..2048).map(|i| (i as f32 * 0.3).sin()).collect();
+        let volume = |stream: &mut Stream| -> f32 {
+            stream.get_frequencies().iter().flatten().map(|f| f.volume).sum()
+        };
+
+        for behavior in [UnderrunBehavior::HoldLast, UnderrunBehavior::DecayToZero, UnderrunBehavior::Zero] {
+            let mut stream = Stream::new(StreamConfig {
+                channel_count: 1,
+                fft_resolution: 1024,
+                gravity: None,
+                underrun_behavior: behavior.clone(),
+                processor: ProcessorConfig { interpolation: Interpolation::None, ..Default::default() },
+                ..Default::default()
+            });
+            stream.push_data(data.clone());
+            stream.update();
+            assert!(!stream.is_starved());
+            let initial = volume(&mut stream);
+            assert!(initial > 0.0);
+
+            // audio stops
+            stream.update();
+            assert!(stream.is_starved());
+            let after = volume(&mut stream);
+            for _ in 0..50 {
+                stream.update();
+            }
+            let later = volume(&mut stream);
+
+            match behavior {
+                UnderrunBehavior::HoldLast => {
+                    assert_eq!(after, initial);
+                    assert_eq!(later, initial);
+                }
+                UnderrunBehavior::DecayToZero => {
+                    assert!(after < initial && after > 0.0);
+                    assert!(later < initial * 0.001);
+                }
+                UnderrunBehavior::Zero => {
+                    assert_eq!(after, 0.0);
+                    assert_eq!(later, 0.0);
+                }
+            }
+
+            stream.push_data(data.clone());
+            stream.update();
+            assert!(!stream.is_starved());
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_max_latency() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+        use std::time::Duration;
+
+        let mut stream = Stream::new(StreamConfig {
+            channel_count: 2,
+            fft_resolution: 1024,
+            max_latency: Some(Duration::from_millis(100)),
+            ..Default::default()
+        });
+        // 2 seconds of stereo audio without updating
+        for _ in 0..20 {
+            stream.push_data(vec![0.5; 4410 * 2]);
+        }
+        assert_eq!(stream.buffered_len(), 4410);
+
+        // never trimmed below what is needed for a frame
+        stream.config.max_latency = Some(Duration::from_millis(1));
+        stream.push_data(vec![0.5; 2]);
+        assert_eq!(stream.buffered_len(), 1025);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn stream_reset_clock() {
+        use crate::spectrum::{config::StreamConfig, stream::Stream};
+
+        let mut stream = Stream::new(StreamConfig { channel_count: 1, fft_resolution: 1024, ..Default::default() });
+        for _ in 0..10 {
+            stream.push_data(vec![0.5; 4410]);
+            stream.update();
+        }
+        let (time, _) = stream.get_frequencies_timed();
+        assert!((time.as_secs_f32() - 1.0).abs() < 1e-3);
+
+        stream.reset_clock();
+        stream.push_data(vec![0.5; 441]);
+        stream.update();
+        let (time, frequencies) = stream.get_frequencies_timed();
+        assert!((time.as_secs_f32() - 0.01).abs() < 1e-3);
+        assert!(!frequencies.is_empty());
+    }
+
+    #[cfg(all(feature = "spectrum", feature = "std"))]
+    #[test]
+    fn onset_intervals() {
+        use crate::spectrum::{onset::OnsetDetector, Frequency};
+        use std::time::{Duration, Instant};
+
+        let silence = vec![Frequency::empty(); 32];
+        let loud: Vec<Frequency> = (0..32).map(|_| Frequency { volume: 1.0, ..Frequency::empty() }).collect();
+
+        let mut detector = OnsetDetector::default();
+        let start = Instant::now();
+        let mut onsets = 0;
+        // 100ms frames, a hit every 500ms
+        for i in 0..40 {
+            let frame = if i % 5 == 0 { &loud } else { &silence };
+            if detector.push_at(frame, start + Duration::from_millis(i * 100)) {
+                onsets += 1;
+            }
+        }
+
+        assert_eq!(onsets, 7);
+        assert_eq!(detector.last_interval(), Some(Duration::from_millis(500)));
+        assert_eq!(detector.intervals().len(), 6);
+        assert!(detector.intervals().iter().all(|i| *i == Duration::from_millis(500)));
+
+        detector.clear();
+        assert_eq!(detector.last_interval(), None);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn prominent_peaks() {
+        use crate::spectrum::{config::ProcessorConfig, processor::Processor};
+
+        let partials: [(f32, f32); 3] = [(440.0, 1.0), (1000.0, 0.6), (2500.0, 0.3)];
+        let signal: Vec<f32> = (0..8192)
+            .map(|i| {
+                let t = i as f32 / 44_100.0 * 2.0 * std::f32::consts::PI;
+                partials.iter().map(|(f, a)| a * (t * f).sin()).sum()
+            })
+            .collect();
+
+        let mut processor = Processor::from_raw_data(ProcessorConfig::default(), signal);
+        processor.apodize();
+        processor.fft();
+        processor.raw_to_freq_buffer();
+
+        let mut peaks = processor.prominent_peaks(3, 50.0);
+        assert_eq!(peaks.len(), 3);
+        peaks.sort_by(|a, b| a.freq.partial_cmp(&b.freq).unwrap());
+        for (peak, (freq, _)) in peaks.iter().zip(partials.iter()) {
+            // one bin is about 5.4hz wide
+            assert!((peak.freq - freq).abs() < 12.0, "{} != {}", peak.freq, freq);
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn compare_configs() {
+        use crate::spectrum::{compare, config::ProcessorConfig};
+
+        let samples: Vec<f32> = (0..2048).map(|i| (i as f32 * 0.05).sin()).collect();
+
+        let config = ProcessorConfig::default();
+        let pairs = compare(&samples, &config, &config);
+        assert!(!pairs.is_empty());
+        for (a, b) in pairs.iter() {
+            assert_eq!(a.volume, b.volume);
+            assert_eq!(a.freq, b.freq);
+            assert_eq!(a.position, b.position);
+        }
+
+        let smaller = ProcessorConfig { resolution: Some(16), ..Default::default() };
+        let larger = ProcessorConfig { resolution: Some(32), ..Default::default() };
+        let pairs = compare(&samples, &smaller, &larger);
+        assert_eq!(pairs.len(), 32);
+        assert_eq!(pairs[31].0.volume, 0.0);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn suppress_frequencies() {
+        use crate::spectrum::{config::ProcessorConfig, processor::Processor};
+
+        // 1024 samples at 1024hz so that every bin is exactly 1hz wide
+        let signal: Vec<f32> = (0..1024)
+            .map(|i| {
+                let t = i as f32 / 1024.0 * 2.0 * std::f32::consts::PI;
+                (t * 100.0).sin() + (t * 200.0).sin()
+            })
+            .collect();
+        let config = ProcessorConfig {
+            sampling_rate: 1024,
+            suppress_frequencies: vec![(200.0, 4.0)],
+            ..Default::default()
+        };
+
+        let mut processor = Processor::from_raw_data(config, signal);
+        processor.apodize();
+        processor.fft();
+        let before = processor.raw_buffer.clone();
+        processor.suppress_frequencies();
+
+        assert!(before[200] > 1.0);
+        for bin in 198..=202 {
+            assert_eq!(processor.raw_buffer[bin], 0.0);
+        }
+        assert_eq!(processor.raw_buffer[197], before[197]);
+        assert_eq!(processor.raw_buffer[203], before[203]);
+        assert!(processor.raw_buffer[100] > 1.0);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn prime_fft_length() {
+        use crate::fft::{is_smooth, next_smooth_len};
+        use crate::spectrum::{config::ProcessorConfig, processor::Processor};
+        use std::time::{Duration, Instant};
+
+        assert!(is_smooth(1024) && is_smooth(44_100) && !is_smooth(1021));
+        assert_eq!(next_smooth_len(1021), 1024);
+        assert_eq!(next_smooth_len(11), 12);
+
+        let data: Vec<f32> = (0..1021).map(|i| (i as f32 * 0.3).sin()).collect();
+        let start = Instant::now();
+        let mut processor = Processor::from_raw_data(ProcessorConfig::default(), data);
+        processor.apodize();
+        processor.fft();
+        assert!(start.elapsed() < Duration::from_millis(50));
+        assert_eq!(processor.raw_buffer.len(), 1024 / 2 + 1);
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn forward_real() {
+        use crate::fft::{forward, forward_real, inverse_real, remove_mirroring};
+
+        for len in [2048, 1000, 7] {
+            let data: Vec<f32> = (0..len).map(|i| (i as f32 * 0.37).sin() + (i as f32 * 0.011).cos() * 0.3).collect();
+
+            let full = remove_mirroring(&forward(&data));
+            let real = forward_real(&data);
+            assert_eq!(full.len(), real.len());
+            for (a, b) in full.iter().zip(real.iter()) {
+                assert!((a.norm() - b.norm()).abs() < 1e-3 * a.norm().max(1.0));
+            }
+
+            let restored = inverse_real(&real, len);
+            assert_eq!(restored.len(), len);
+            for (x, y) in data.iter().zip(restored.iter()) {
+                assert!((x - y / len as f32).abs() < 1e-4);
+            }
+        }
+
+        // processor gives same result with real fft
+        let data: Vec<f32> = (0..2048).map(|i| (i as f32 * 0.2).sin()).collect();
+        let spectrum = |real_fft: bool| -> Vec<f32> {
+            let config = crate::spectrum::config::ProcessorConfig { real_fft, ..Default::default() };
+            let mut processor = crate::spectrum::processor::Processor::from_raw_data(config, data.clone());
+            processor.fft();
+            processor.raw_buffer
+        };
+        for (a, b) in spectrum(false).iter().zip(spectrum(true).iter()) {
+            assert!((a - b).abs() < 1e-3 * a.max(1.0));
+        }
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn fft_plan_cache() {
+        use crate::spectrum::{config::ProcessorConfig, processor::Processor};
+
+        // tests run on their own thread, so the counter is not affected by other tests
+        let data: Vec<f32> = (0..2048).map(|i| (i as f32 * 0.1).sin()).collect();
+        let before = crate::fft::plans_created();
+        for _ in 0..1000 {
+            let mut processor = Processor::from_raw_data(ProcessorConfig::default(), data.clone());
+            processor.fft();
+        }
+        assert_eq!(crate::fft::plans_created() - before, 1);
+
+        crate::fft::forward(&data[..1024]);
+        assert_eq!(crate::fft::plans_created() - before, 2);
+
+        // f64 has its own cache
+        let data: Vec<f64> = data.iter().map(|x| *x as f64).collect();
+        for _ in 0..10 {
+            crate::fft::forward_f64(&data);
+        }
+        assert_eq!(crate::fft::plans_created() - before, 3);
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn phase_vocoder() {
+        use crate::fft::phase_vocoder;
+
+        let signal: Vec<f32> = sine(440.0, 44_100.0, 8192).into_iter().map(|x| x * 0.5).collect();
+
+        let same = phase_vocoder(&signal, 1.0, 1024, 256);
+        assert_eq!(same.len(), signal.len());
+        // edges are only covered by the faded out parts of the window
+        for (x, y) in same[1024..7168].iter().zip(signal[1024..7168].iter()) {
+            assert!((x - y).abs() < 1e-3, "{} != {}", x, y);
+        }
+
+        let stretched = phase_vocoder(&signal, 2.0, 1024, 256);
+        assert_eq!(stretched.len(), signal.len() * 2);
+        let rms = |data: &[f32]| (data.iter().map(|x| x * x).sum::<f32>() / data.len() as f32).sqrt();
+        assert!((rms(&stretched[2048..14336]) - rms(&signal)).abs() < 0.05);
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn stft() {
+        use crate::fft::Stft;
+
+        // sine exactly on bin 32 of a 512 point fft
+        let signal: Vec<f32> = sine(32.0, 512.0, 4096);
+
+        let mut stft = Stft::new(512, 128);
+        let matrix = stft.process(&signal);
+        assert_eq!(matrix.len(), 1 + (4096 - 512) / 128);
+        assert_eq!(matrix.len(), stft.frame_count(signal.len()));
+
+        for frame in matrix.iter() {
+            assert_eq!(frame.len(), 257);
+            let peak = frame
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.norm().partial_cmp(&b.1.norm()).unwrap())
+                .unwrap()
+                .0;
+            assert_eq!(peak, 32);
+        }
+
+        assert!(stft.process(&signal[..100]).is_empty());
+    }
+
+    #[cfg(feature = "spectrum")]
+    #[test]
+    fn dc_component() {
+        use crate::spectrum::{config::ProcessorConfig, processor::Processor};
+
+        let sine: Vec<f32> = sine(64.0, 1024.0, 1024);
+        let offset: Vec<f32> = sine.iter().map(|x| x + 0.5).collect();
+
+        let mut processor = Processor::from_raw_data(ProcessorConfig::default(), sine);
+        processor.apodize();
+        processor.fft();
+        assert!(processor.dc_component().abs() < 0.01);
+
+        let mut processor = Processor::from_raw_data(ProcessorConfig::default(), offset);
+        processor.apodize();
+        processor.fft();
+        // hann window halves the mean
+        assert!((processor.dc_component() - 0.25).abs() < 0.01);
+        assert!(processor.nyquist_component().abs() < 0.01);
+
+        // alternating signal has all its energy at nyquist
+        let alternating: Vec<f32> = (0..256).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        let mut processor = Processor::from_raw_data(ProcessorConfig::default(), alternating);
+        processor.fft();
+        assert!((processor.nyquist_component() - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn loudness_lufs() {
+        use crate::utils::loudness_lufs;
+
+        let sample_rate: f32 = 48_000.0;
+        let tone = |amplitude: f32| -> Vec<f32> {
+            sine(997.0, sample_rate, 48_000).into_iter().map(|x| amplitude * x).collect()
+        };
+
+        // -20 dBFS sine
+        let lufs = loudness_lufs(&tone(0.1), sample_rate);
+        assert!((lufs - -23.0).abs() < 0.5, "{}", lufs);
+
+        let lufs = loudness_lufs(&tone(1.0), 44_100.0);
+        assert!((lufs - -3.0).abs() < 0.5, "{}", lufs);
+
+        assert_eq!(loudness_lufs(&[0.0; 128], sample_rate), f32::NEG_INFINITY);
+    }
+}
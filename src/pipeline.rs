@@ -0,0 +1,153 @@
+//! ## Example
+//! ```no_run
+//! use audioviz::audio_capture::capture::Device;
+//! use audioviz::spectrum::config::StreamConfig;
+//! use audioviz::pipeline::Pipeline;
+//!
+//! let pipeline = Pipeline::builder()
+//!     .device(Device::DefaultInput)
+//!     .stream_config(StreamConfig::default())
+//!     .build()
+//!     .unwrap();
+//!
+//! loop {
+//!     let frequencies = pipeline.get_frequencies();
+//!     // draw frequencies
+//! }
+//! ```
+
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
+use std::thread;
+use std::time::Duration;
+
+use crate::audio_capture::capture::{Capture, Device, Error};
+use crate::distributor::Distributor;
+use crate::spectrum::{Frequency, config::StreamConfig, stream::Stream};
+
+/// glue thread never sleeps shorter than this, even with very high `refresh_rate`
+///
+/// prevents the thread from hogging the stream lock and starving the capture side
+pub const MIN_REFRESH_INTERVAL: Duration = Duration::from_millis(1);
+
+/// time between two updates of the glue thread for `refresh_rate`
+pub(crate) fn refresh_interval(refresh_rate: usize) -> Duration {
+    Duration::from_secs_f64(1.0 / refresh_rate.max(1) as f64).max(MIN_REFRESH_INTERVAL)
+}
+
+/// builder of `Pipeline`, created with `Pipeline::builder()`
+pub struct PipelineBuilder {
+    device: Device,
+    stream_config: StreamConfig,
+}
+impl PipelineBuilder {
+    /// device to capture from, defaults to `Device::DefaultInput`
+    pub fn device(mut self, device: Device) -> Self {
+        self.device = device;
+        self
+    }
+
+    /// config of the inner stream
+    ///
+    /// `channel_count` and `sampling_rate` of all processor configs get overwritten by the values of the captured device
+    pub fn stream_config(mut self, config: StreamConfig) -> Self {
+        self.stream_config = config;
+        self
+    }
+
+    /// initializes capture and spawns the thread that feeds the stream
+    pub fn build(self) -> Result<Pipeline, Error> {
+        let mut capture = Capture::new();
+        capture.init(&self.device)?;
+        let receiver = capture.get_receiver().ok_or(Error::DeviceNotAvailable)?;
+
+        let channel_count: u16 = capture.channel_count.unwrap_or(1).max(1);
+        let sampling_rate: u32 = capture.sampling_rate.unwrap_or(44_100);
+
+        let mut config = self.stream_config;
+        config.channel_count = channel_count;
+        config.processor.sampling_rate = sampling_rate;
+        for processor in config.per_channel_processor.iter_mut().flatten() {
+            processor.sampling_rate = sampling_rate;
+        }
+        // cached once, so the glue thread never has to ask for the config again
+        let interval = refresh_interval(config.refresh_rate);
+
+        // enough for two FFTs of every channel
+        let max_buffer_length: usize = config.fft_resolution * channel_count as usize * 2;
+        let mut distributor: Distributor<f32> = Distributor::new(
+            sampling_rate as f64 * channel_count as f64,
+            Some(max_buffer_length),
+        );
+        distributor.set_granularity(channel_count as usize);
+
+        let stream = Arc::new(Mutex::new(Stream::new(config)));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread_stream = stream.clone();
+        let thread_running = running.clone();
+        thread::spawn(move || {
+            while thread_running.load(Ordering::Relaxed) {
+                if let Some(data) = receiver.receive_data() {
+                    distributor.push_auto(&data);
+                }
+                let data = distributor.pop_auto(None);
+                if let Ok(mut stream) = thread_stream.lock() {
+                    stream.push_data(data);
+                    stream.update();
+                }
+                thread::sleep(interval);
+            }
+        });
+
+        Ok(Pipeline {
+            capture,
+            stream,
+            running,
+        })
+    }
+}
+
+/// `Capture`, `Distributor` and `Stream` wired together
+///
+/// distributor is sized to the data rate of the captured device (`sampling_rate * channel_count`),
+/// holds at most two FFTs worth of data of every channel (`fft_resolution * channel_count * 2`)
+/// and only gives away complete frames.
+/// stream gets updated with `refresh_rate` of its config on a seperate thread,
+/// but at most once per `MIN_REFRESH_INTERVAL`,
+/// which stops when `Pipeline` gets dropped
+pub struct Pipeline {
+    capture: Capture,
+    stream: Arc<Mutex<Stream>>,
+    running: Arc<AtomicBool>,
+}
+impl Pipeline {
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder {
+            device: Device::DefaultInput,
+            stream_config: StreamConfig::default(),
+        }
+    }
+
+    /// see `Stream::get_frequencies()`
+    pub fn get_frequencies(&self) -> Vec<Vec<Frequency>> {
+        match self.stream.lock() {
+            Ok(mut stream) => stream.get_frequencies(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// underlying capture, for example to query `channel_count` and `sampling_rate`
+    pub fn capture(&self) -> &Capture {
+        &self.capture
+    }
+
+    /// underlying stream, locked while the feeding thread is not using it
+    pub fn stream(&self) -> Arc<Mutex<Stream>> {
+        self.stream.clone()
+    }
+}
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
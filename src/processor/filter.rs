@@ -66,17 +66,16 @@ pub fn lowpass_filter(data: &[f32], sampling_rate: f32, cutoff_start_freq: f32,
     let step: f32 = PI / diff as f32;
 
     let mut position: f32 = 0.0;
-    for i in start..=end {
+    for bin in spectrum[start..=end].iter_mut() {
         let mul = (position.cos() + 1.0) / 2.0;
-        spectrum[i] *= mul;
-        spectrum[len - i - 1] *= mul;
+        *bin *= mul;
 
         position += step;
     }
-    for i in end..spectrum_len {
-        spectrum[i] *= 0.0;
-        spectrum[len - i - 1] *= 0.0;
+    for bin in spectrum[end..=spectrum_len].iter_mut() {
+        *bin *= 0.0;
     }
+    fft::enforce_conjugate_symmetry(&mut spectrum);
 
     let data = fft::inverse(&spectrum);
 
@@ -102,17 +101,16 @@ pub fn highpass_filter(data: &[f32], sampling_rate: f32, cutoff_start_freq: f32,
     let step: f32 = PI / diff as f32;
 
     let mut position: f32 = PI;
-    for i in start..=end {
+    for bin in spectrum[start..=end].iter_mut() {
         let mul = (position.cos() + 1.0) / 2.0;
-        spectrum[i] *= mul;
-        spectrum[len - i - 1] *= mul;
+        *bin *= mul;
 
         position -= step;
     }
-    for i in 0..=start {
-        spectrum[i] *= 0.0;
-        spectrum[len - i - 1] *= 0.0;
+    for bin in spectrum[0..=start].iter_mut() {
+        *bin *= 0.0;
     }
+    fft::enforce_conjugate_symmetry(&mut spectrum);
 
     let data = fft::inverse(&spectrum);
 
@@ -157,36 +155,227 @@ pub fn bandpass_filter(
     // smooth transition between cut and not cut freqs
     // lowcut
     let mut low_position: f32 = PI;
-    for i in low_start..=low_end {
+    for bin in spectrum[low_start..=low_end].iter_mut() {
         let mul = (low_position.cos() + 1.0) / 2.0;
-        spectrum[i] *= mul;
-        spectrum[len - i - 1] *= mul;
+        *bin *= mul;
 
         low_position -= low_step;
     }
     // highcut
     let mut high_position: f32 = 0.0;
-    for i in high_start..=high_end {
+    for bin in spectrum[high_start..=high_end].iter_mut() {
         let mul = (high_position.cos() + 1.0) / 2.0;
-        spectrum[i] *= mul;
-        spectrum[len - i - 1] *= mul;
+        *bin *= mul;
 
         high_position += high_step;
     }
 
     // mutes freqs that are beyond threshold
     // left from lowcut
-    for i in 0..=low_start {
-        spectrum[i] *= 0.0;
-        spectrum[len - i - 1] *= 0.0;
+    for bin in spectrum[0..=low_start].iter_mut() {
+        *bin *= 0.0;
     }
     // right from highcut
-    for i in high_end..=spectrum_len {
-        spectrum[i] *= 0.0;
-        spectrum[len - i - 1] *= 0.0;
+    for bin in spectrum[high_end..=spectrum_len].iter_mut() {
+        *bin *= 0.0;
     }
+    fft::enforce_conjugate_symmetry(&mut spectrum);
 
     let data = fft::inverse(&spectrum);
 
     fft::get_real(&data)
 }
+
+/// inverse of `bandpass_filter`, mutes everything between `low_cutoff_end_freq` and `high_cutoff_start_freq`
+///
+/// volume falls from `low_cutoff_start_freq` to `low_cutoff_end_freq` and rises again
+/// from `high_cutoff_start_freq` to `high_cutoff_end_freq`, useful to remove narrow interference like mains hum
+pub fn notch_filter(
+    data: &[f32],
+    sampling_rate: f32,
+    low_cutoff_start_freq: f32,
+    low_cutoff_end_freq: f32,
+    high_cutoff_start_freq: f32,
+    high_cutoff_end_freq: f32,
+) -> Vec<f32> {
+    assert!(low_cutoff_end_freq >= low_cutoff_start_freq);
+    assert!(high_cutoff_start_freq >= low_cutoff_end_freq);
+    assert!(high_cutoff_end_freq >= high_cutoff_start_freq);
+
+    assert!(low_cutoff_start_freq <= sampling_rate / 2.0 && low_cutoff_end_freq <= sampling_rate / 2.0);
+    assert!(high_cutoff_start_freq <= sampling_rate / 2.0 && high_cutoff_end_freq <= sampling_rate / 2.0);
+
+    let len = data.len();
+    let spectrum_len = len / 2;
+
+    let mut spectrum = fft::forward(data);
+    assert!(len == spectrum.len());
+
+    let low_start: usize = (spectrum_len as f32 * (low_cutoff_start_freq / sampling_rate * 2.0)) as usize;
+    let low_end: usize = (spectrum_len as f32 * (low_cutoff_end_freq / sampling_rate * 2.0)) as usize;
+    let low_diff = low_end - low_start;
+
+    let high_start: usize = (spectrum_len as f32 * (high_cutoff_start_freq / sampling_rate * 2.0)) as usize;
+    let high_end: usize = (spectrum_len as f32 * (high_cutoff_end_freq / sampling_rate * 2.0)) as usize;
+    let high_diff = high_end - high_start;
+
+    // what to add to low_position in each iteration
+    let low_step: f32 = PI / low_diff as f32;
+
+    // what to subtract from high_position in each iteration
+    let high_step: f32 = PI / high_diff as f32;
+
+    // smooth transition between cut and not cut freqs
+    // falling edge
+    let mut low_position: f32 = 0.0;
+    for bin in spectrum[low_start..=low_end].iter_mut() {
+        let mul = (low_position.cos() + 1.0) / 2.0;
+        *bin *= mul;
+
+        low_position += low_step;
+    }
+    // rising edge
+    let mut high_position: f32 = PI;
+    for bin in spectrum[high_start..=high_end].iter_mut() {
+        let mul = (high_position.cos() + 1.0) / 2.0;
+        *bin *= mul;
+
+        high_position -= high_step;
+    }
+
+    // mutes freqs inside of notch
+    for bin in spectrum[low_end..=high_start].iter_mut() {
+        *bin *= 0.0;
+    }
+    fft::enforce_conjugate_symmetry(&mut spectrum);
+
+    let data = fft::inverse(&spectrum);
+
+    fft::get_real(&data)
+}
+
+/// boosts or cuts frequencies around `center_freq` by up to `gain_db` decibels
+///
+/// gain follows a gaussian curve, every bin at `f` hz gets multiplied by `10^(gain_db * shape / 20)`
+/// with `shape = e^(-((f - center_freq) / bandwidth)^2 / 2)`,
+/// so `bandwidth` is the distance in hz at which gain has fallen to about 60% of `gain_db`
+pub fn peak_filter(data: &[f32], sampling_rate: f32, center_freq: f32, bandwidth: f32, gain_db: f32) -> Vec<f32> {
+    assert!(center_freq >= 0.0 && center_freq <= sampling_rate / 2.0);
+    assert!(bandwidth > 0.0);
+
+    let len = data.len();
+    let spectrum_len = len / 2;
+
+    let mut spectrum = fft::forward(data);
+    assert!(len == spectrum.len());
+
+    for (i, bin) in spectrum[0..=spectrum_len].iter_mut().enumerate() {
+        let freq: f32 = i as f32 * sampling_rate / len as f32;
+        let shape: f32 = (-((freq - center_freq) / bandwidth).powi(2) / 2.0).exp();
+        *bin *= 10_f32.powf(gain_db * shape / 20.0);
+    }
+    fft::enforce_conjugate_symmetry(&mut spectrum);
+
+    let data = fft::inverse(&spectrum);
+
+    fft::get_real(&data)
+}
+
+/// coefficients `[b0, b1, b2, a1, a2]` of a biquad lowpass, normalized by `a0`
+///
+/// taken from: "https://www.w3.org/TR/audio-eq-cookbook/"
+pub fn biquad_lowpass_coefficients(sampling_rate: f32, cutoff_freq: f32, q: f32) -> [f32; 5] {
+    assert!(cutoff_freq > 0.0 && cutoff_freq < sampling_rate / 2.0);
+    assert!(q > 0.0);
+
+    let w0 = 2.0 * PI * cutoff_freq / sampling_rate;
+    let alpha = w0.sin() / (2.0 * q);
+    let cos = w0.cos();
+
+    let a0 = 1.0 + alpha;
+    [
+        (1.0 - cos) / 2.0 / a0,
+        (1.0 - cos) / a0,
+        (1.0 - cos) / 2.0 / a0,
+        -2.0 * cos / a0,
+        (1.0 - alpha) / a0,
+    ]
+}
+
+/// applies biquad with `coefficients` to `data` sample by sample
+///
+/// `state` holds the last two inputs and outputs `[x1, x2, y1, y2]` and carries over to the next call,
+/// so consecutive blocks of a stream get filtered without discontinuities
+pub fn biquad_filter(data: &[f32], coefficients: &[f32; 5], state: &mut [f32; 4]) -> Vec<f32> {
+    let [b0, b1, b2, a1, a2] = *coefficients;
+
+    data.iter()
+        .map(|x| {
+            let [x1, x2, y1, y2] = *state;
+            let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+            *state = [*x, x1, y, y1];
+            y
+        })
+        .collect()
+}
+
+/// group delay in samples at low frequencies of biquad with `coefficients`
+///
+/// unlike the FFT based filters a biquad is not linear phase, so its delay depends on frequency.
+/// near DC it equals the centroid of the impulse response, which for a lowpass is a bit later than its peak
+pub fn biquad_group_delay(coefficients: &[f32; 5]) -> f32 {
+    let [b0, b1, b2, a1, a2] = coefficients.map(|c| c as f64);
+
+    // negative derivative of phase at w = 0 of numerator and denominator
+    let numerator = (b1 + 2.0 * b2) / (b0 + b1 + b2);
+    let denominator = (a1 + 2.0 * a2) / (1.0 + a1 + a2);
+    (numerator - denominator) as f32
+}
+
+/// replaces subnormal floats in `data` with zero
+///
+/// subnormals can appear after filtering silence and are very slow to compute with on many x86 cpus.
+/// this is done in software, because hardware flush-to-zero is platform specific and affects the whole thread
+pub fn flush_denormals(data: &mut [f32]) {
+    for x in data.iter_mut() {
+        if x.is_subnormal() {
+            *x = 0.0;
+        }
+    }
+}
+
+/// adds an inaudible offset of alternating sign to `data`, so that following filters never operate on subnormal values
+///
+/// `amplitude` should be far above `f32::MIN_POSITIVE` and far below audible levels, for example `1e-20`
+pub fn add_dither(data: &mut [f32], amplitude: f32) {
+    for (i, x) in data.iter_mut().enumerate() {
+        if i % 2 == 0 {
+            *x += amplitude;
+        } else {
+            *x -= amplitude;
+        }
+    }
+}
+
+/// delay introduced by filters in samples
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FilterLatency {
+    /// shift of filtered signal relative to input
+    pub group_delay: usize,
+
+    /// samples that have to be collected before block can be filtered
+    pub block_delay: usize,
+}
+
+/// latency of `lowpass_filter`, `highpass_filter` and `bandpass_filter`
+///
+/// these filters are applied block-wise in frequency domain and only scale the magnitude of each bin,
+/// so they are zero-phase and do not shift the signal inside a block.
+///
+/// when filtering a realtime stream the whole block must be collected first, which is reported as `block_delay`
+pub fn filter_latency(block_length: usize) -> FilterLatency {
+    FilterLatency {
+        group_delay: 0,
+        block_delay: block_length,
+    }
+}
@@ -1,5 +1,8 @@
 pub mod filter;
-use filter::{lowpass_filter, highpass_filter, bandpass_filter};
+use filter::{
+    lowpass_filter, highpass_filter, bandpass_filter, notch_filter, peak_filter, biquad_lowpass_coefficients, biquad_filter, biquad_group_delay,
+    flush_denormals, add_dither, filter_latency, FilterLatency,
+};
 
 #[derive(Copy, Clone, Debug)]
 pub struct Lowpass {
@@ -49,11 +52,81 @@ pub struct Bandpass {
     }
 }
 
+/// band-stop, inverse of `Bandpass`
+#[derive(Copy, Clone, Debug)]
+pub struct Notch {
+    pub low_cutoff_start_freq: f32,
+    pub low_cutoff_end_freq: f32,
+    pub high_cutoff_start_freq: f32,
+    pub high_cutoff_end_freq: f32,
+} impl Notch {
+    pub fn new(
+        low_cutoff_start_freq: f32,
+        low_cutoff_end_freq: f32,
+        high_cutoff_start_freq: f32,
+        high_cutoff_end_freq: f32
+    ) -> Self {
+        Self {
+            low_cutoff_start_freq,
+            low_cutoff_end_freq,
+            high_cutoff_start_freq,
+            high_cutoff_end_freq
+        }
+    }
+}
+
+/// stateful lowpass, that is applied in time domain and keeps its history between calls to `Processor::process()`
+#[derive(Copy, Clone, Debug)]
+pub struct Biquad {
+    pub cutoff_freq: f32,
+    pub q: f32,
+    state: [f32; 4],
+} impl Biquad {
+    pub fn new(cutoff_freq: f32, q: f32) -> Self {
+        Self {
+            cutoff_freq,
+            q,
+            state: [0.0; 4],
+        }
+    }
+
+    /// clears history of previous inputs and outputs
+    pub fn reset_state(&mut self) {
+        self.state = [0.0; 4];
+    }
+}
+
+/// protection against slow subnormal floats during sustained silence
+#[derive(Copy, Clone, Debug)]
+pub enum Denormals {
+    /// sets subnormal samples to zero, should be placed after filters
+    FlushToZero,
+
+    /// adds dither of given amplitude, should be placed before filters
+    Dither(f32),
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum Plugin {
     Lowpass(Lowpass),
     Highpass(Highpass),
-    Bandpass(Bandpass)
+    Bandpass(Bandpass),
+    Notch(Notch),
+    /// boosts or cuts a band by `gain_db` around `center_freq`, see `filter::peak_filter()`
+    Peak {
+        center_freq: f32,
+        bandwidth: f32,
+        gain_db: f32,
+    },
+    Biquad(Biquad),
+    Denormals(Denormals),
+} impl Plugin {
+    /// clears internal state of stateful filters, no-op for FFT based filters
+    pub fn reset_state(&mut self) {
+        if let Plugin::Biquad(biquad) = self {
+            biquad.reset_state();
+        }
+    }
 }
 
 pub struct Processor {
@@ -62,8 +135,39 @@ pub struct Processor {
     pub plugins: Vec<Plugin>,
 }
 impl Processor {
+    /// latency that `process()` introduces with current `data` length
+    ///
+    /// `group_delay` is the sum of the low frequency group delays of all `Plugin::Biquad`, rounded to whole samples,
+    /// see `filter::biquad_group_delay()`
+    pub fn latency(&self) -> FilterLatency {
+        let group_delay: f32 = self.plugins
+            .iter()
+            .map(|plugin| match plugin {
+                Plugin::Biquad(biquad) => {
+                    biquad_group_delay(&biquad_lowpass_coefficients(self.sampling_rate, biquad.cutoff_freq, biquad.q))
+                },
+                _ => 0.0,
+            })
+            .sum();
+
+        FilterLatency {
+            group_delay: group_delay.round().max(0.0) as usize,
+            ..filter_latency(self.data.len())
+        }
+    }
+
+    /// resets state of all plugins
+    ///
+    /// should be called when switching to a different source,
+    /// otherwise history of the old source causes a transient at the start of the new one
+    pub fn reset_filters(&mut self) {
+        for plugin in self.plugins.iter_mut() {
+            plugin.reset_state();
+        }
+    }
+
     pub fn process(&mut self) {
-        for plugin in self.plugins.iter() {
+        for plugin in self.plugins.iter_mut() {
             match plugin {
                 Plugin::Lowpass(lowpass) => {
                     self.data = lowpass_filter(&self.data, self.sampling_rate, lowpass.cutoff_start_freq, lowpass.cutoff_end_freq)
@@ -80,7 +184,26 @@ impl Processor {
                         bandpass.high_cutoff_start_freq,
                         bandpass.high_cutoff_end_freq,
                     )
-                }
+                },
+                Plugin::Notch(notch) => {
+                    self.data = notch_filter(
+                        &self.data,
+                        self.sampling_rate,
+                        notch.low_cutoff_start_freq,
+                        notch.low_cutoff_end_freq,
+                        notch.high_cutoff_start_freq,
+                        notch.high_cutoff_end_freq,
+                    )
+                },
+                Plugin::Peak { center_freq, bandwidth, gain_db } => {
+                    self.data = peak_filter(&self.data, self.sampling_rate, *center_freq, *bandwidth, *gain_db)
+                },
+                Plugin::Biquad(biquad) => {
+                    let coefficients = biquad_lowpass_coefficients(self.sampling_rate, biquad.cutoff_freq, biquad.q);
+                    self.data = biquad_filter(&self.data, &coefficients, &mut biquad.state)
+                },
+                Plugin::Denormals(Denormals::FlushToZero) => flush_denormals(&mut self.data),
+                Plugin::Denormals(Denormals::Dither(amplitude)) => add_dither(&mut self.data, *amplitude),
             }
         }
     }
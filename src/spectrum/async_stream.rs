@@ -0,0 +1,99 @@
+//! ## Example
+//! ```
+//! use audioviz::spectrum::{config::StreamConfig, stream::Stream, async_stream::AsyncStream};
+//!
+//! let stream = AsyncStream::new(Stream::new(StreamConfig::default()));
+//!
+//! // feeding can happen on any thread
+//! let inner = stream.stream();
+//! std::thread::spawn(move || {
+//!     let mut stream = inner.lock().unwrap();
+//!     stream.push_data(vec![0.0; 8192]);
+//!     stream.update();
+//! });
+//!
+//! async {
+//!     let frequencies = stream.get_frequencies().await;
+//! };
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use super::{Frequency, stream::Stream};
+
+#[derive(Default)]
+struct FrameState {
+    ready: bool,
+    waker: Option<Waker>,
+}
+
+/// `Stream` whose frequencies can be awaited
+///
+/// inner stream still has to be fed with `push_data()` and `update()`, for example on a seperate thread,
+/// `get_frequencies()` then resolves on the next computed frame.
+/// works with any async runtime, because waking is done from `Stream::set_on_frame()`,
+/// which means that callback must not be replaced
+pub struct AsyncStream {
+    stream: Arc<Mutex<Stream>>,
+    state: Arc<Mutex<FrameState>>,
+}
+impl AsyncStream {
+    pub fn new(mut stream: Stream) -> Self {
+        let state = Arc::new(Mutex::new(FrameState::default()));
+
+        let callback_state = state.clone();
+        stream.set_on_frame(move |_| {
+            if let Ok(mut state) = callback_state.lock() {
+                state.ready = true;
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+        });
+
+        Self {
+            stream: Arc::new(Mutex::new(stream)),
+            state,
+        }
+    }
+
+    /// inner stream, that has to be fed with data
+    pub fn stream(&self) -> Arc<Mutex<Stream>> {
+        self.stream.clone()
+    }
+
+    /// waits until a new frame was computed since the last call and returns its frequencies
+    pub fn get_frequencies(&self) -> NextFrame<'_> {
+        NextFrame { stream: self }
+    }
+}
+
+/// future returned by `AsyncStream::get_frequencies()`
+pub struct NextFrame<'a> {
+    stream: &'a AsyncStream,
+}
+impl Future for NextFrame<'_> {
+    type Output = Vec<Vec<Frequency>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        {
+            let mut state = match self.stream.state.lock() {
+                Ok(s) => s,
+                Err(_) => return Poll::Ready(Vec::new()),
+            };
+            if !state.ready {
+                state.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+            state.ready = false;
+        }
+
+        match self.stream.stream.lock() {
+            Ok(mut stream) => Poll::Ready(stream.get_frequencies()),
+            Err(_) => Poll::Ready(Vec::new()),
+        }
+    }
+}
@@ -1,8 +1,12 @@
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use std::time::Duration;
+
+use crate::utils::{note_to_freq, NoteError};
+
 // I know it can be replaced with Option<>, but I want to add things in the future
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum VolumeNormalisation {
     None,
@@ -13,15 +17,136 @@ pub enum VolumeNormalisation {
     Mixture,
 }
 
-#[derive(Debug, Clone)]
+/// scale of volumes in `freq_buffer`, applied in `Processor::raw_to_freq_buffer()` before interpolation
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum PositionNormalisation {
+pub enum VolumeScale {
+    /// magnitude as it is
     Linear,
+
+    /// magnitude in dBFS, where 0 dB is a full scale sine,
+    /// clamped to `floor_db` (for example -90.0) and mapped to (0..=1) with `floor_db` as 0.0
+    ///
+    /// full scale reference takes window and `amplitude_mode` into account, but no volume normalisation,
+    /// which is why it is best combined with `VolumeNormalisation::None`
+    Decibel { floor_db: f32 },
+}
+
+/// window function that gets applied in `Processor::apodize()`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Window {
+    Hann,
+
+    /// does not fall to zero at the edges, lower first sidelobe than `Hann`
+    Hamming,
+
+    /// classic blackman window, stronger edge attenuation than `Hann`
+    Blackman,
+
+    /// 4-term blackman-harris window, very low sidelobes for tonal analysis
+    BlackmanHarris,
+
+    /// no windowing at all, useful for transients
+    Rectangular,
+}
+impl Window {
+    /// coefficients of window with `size` samples
+    pub fn coefficients(&self, size: usize) -> Vec<f32> {
+        self.coefficients_f64(size).iter().map(|w| *w as f32).collect()
+    }
+
+    /// same as `coefficients()` with f64 precision
+    pub fn coefficients_f64(&self, size: usize) -> Vec<f64> {
+        if size < 2 {
+            return vec![1.0; size];
+        }
+        match self {
+            Window::Hann => apodize::hanning_iter(size).collect(),
+            Window::Hamming => apodize::hamming_iter(size).collect(),
+            Window::Blackman => apodize::cosine_iter(0.42, 0.5, 0.08, 0.0, size).collect(),
+            // `apodize::blackman_iter()` uses blackman-harris coefficients
+            Window::BlackmanHarris => apodize::blackman_iter(size).collect(),
+            Window::Rectangular => vec![1.0; size],
+        }
+    }
+}
+
+/// how complex FFT bins are reduced to real volumes in `raw_buffer`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AmplitudeMode {
+    /// magnitude `|x|` of complex bin
+    Magnitude,
+
+    /// power `|x|^2`, magnitude squared
+    Power,
+
+    /// `sqrt(|x|)`, square root of magnitude, which compresses dynamic range
+    Sqrt,
+}
+impl AmplitudeMode {
+    /// maps `magnitude` of a complex bin according to mode
+    pub fn apply(&self, magnitude: f64) -> f64 {
+        match self {
+            AmplitudeMode::Magnitude => magnitude,
+            AmplitudeMode::Power => magnitude * magnitude,
+            AmplitudeMode::Sqrt => magnitude.sqrt(),
+        }
+    }
+}
+
+/// former name of `FrequencyAxis`, all of its variants are still there
+#[deprecated(note = "use `FrequencyAxis` and `ProcessorConfig::frequency_axis` instead")]
+pub type PositionNormalisation = FrequencyAxis;
+
+/// smallest gamma of `FrequencyAxis::Gamma`,
+/// at 0.0 every frequency would be at position 1.0 and negative values would be infinite
+pub const MIN_GAMMA: f32 = 0.01;
+
+/// spacing of frequencies along the horizontal axis
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FrequencyAxis {
+    /// equal space for equal distance in hz
+    Linear,
+
+    /// equal space for every octave
+    Logarithmic,
+
+    /// mel scale, `2595 * log10(1 + f / 700)`
+    Mel,
+
+    /// bark scale after Traunmüller, `26.81 * f / (1960 + f) - 0.53`
+    Bark,
+
+    /// `position = sqrt(position)`
     Exponential,
+
+    /// every FFT bin `n` gets a space of `1 / n`
     Harmonic,
+
+    /// `position = position^gamma`, a continuous knob between the other curves
+    ///
+    /// 1.0 is `Linear`, 0.5 is `Exponential`, lower values give even more space to low frequencies.
+    /// gamma must be positive, values below `MIN_GAMMA` are clamped to it
+    Gamma(f32),
+}
+impl FrequencyAxis {
+    /// position of `hz` on scale of axis, before normalisation to (0..=1)
+    ///
+    /// `Exponential`, `Harmonic` and `Gamma` do not depend on frequency and are treated as `Linear` here
+    pub fn warp(&self, hz: f64) -> f64 {
+        match self {
+            FrequencyAxis::Logarithmic => hz.max(f64::MIN_POSITIVE).ln(),
+            FrequencyAxis::Mel => 2595.0 * (1.0 + hz / 700.0).log10(),
+            FrequencyAxis::Bark => 26.81 * hz / (1960.0 + hz) - 0.53,
+            _ => hz,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Interpolation {
     /// Not recommended
@@ -78,7 +203,7 @@ pub enum Interpolation {
     Gaps,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ProcessorConfig {
     /// neccessary so that the Audiostream knows what the hightest frequency is. (`sampling_rate` / 2)
@@ -87,72 +212,300 @@ pub struct ProcessorConfig {
     /// range of frequencies
     pub frequency_bounds: [usize; 2],
 
+    /// only FFT bins within `frequency_bounds` get normalised and converted to frequencies,
+    /// which saves work when the displayed range is narrow compared to `sampling_rate`
+    ///
+    /// FFT itself and its magnitudes still cover the whole spectrum, because rustfft can not compute partial spectra.
+    /// output is the same, except that plugins only see frequencies within bounds.
+    /// has no effect if `manual_position_distribution` is set
+    pub restrict_to_bounds: bool,
+
     /// number of total frequencies in processed data, None to disable up or downscaling
     /// 
-    /// when `position_normalisation` and `resolution` is `None` no frequency information is lost
+    /// when `frequency_axis` is `Linear` and `resolution` is `None` no frequency information is lost
     /// 
-    /// but when `position_normalisation` is set to anything else,
+    /// but when `frequency_axis` is set to anything else,
     /// information will be lost on high frequencies if no upscaling is done.
     pub resolution: Option<usize>,
 
     pub volume: f32,
 
+    /// window function of `Processor::apodize()`
+    pub window: Window,
+
+    /// reduction of complex FFT bins, applied in `fft()` before any normalisation
+    pub amplitude_mode: AmplitudeMode,
+
+    /// uses `fft::forward_real()` in `Processor::fft()`, which is about twice as fast for even lengths
+    /// and only differs by rounding errors
+    pub real_fft: bool,
+
     /// to even volume of low and high frequencies
     pub volume_normalisation: VolumeNormalisation,
 
-    /// to mimic human hearing
+    /// linear or decibel volumes
+    pub volume_scale: VolumeScale,
+
+    /// factor that maps magnitudes relative to a full scale sine to volumes, set by `Processor::calibrate()`
+    ///
+    /// with `Some` a sine with an amplitude of 1.0 at the calibrated frequency reads as volume 1.0,
+    /// independent of `fft_resolution` and window, like this volumes are comparable across runs.
+    /// gets applied in `Processor::raw_to_freq_buffer()` before `volume`
+    pub calibration: Option<f32>,
+
+    /// spacing of frequencies, to mimic human hearing
     /// 
     /// might result in information loss on higher frequencies
-    pub position_normalisation: PositionNormalisation,
+    pub frequency_axis: FrequencyAxis,
 
     /// manually apply scale of frequencies
     ///
     /// frequencies around 50hz have double the scale: `vec![ (0, 1.0), (50, 2.0), (20000, 1.0) ]`
     ///
     /// this can be applied to an infinite number of frequencies: `vec![ (20, 1.0), (500, 2.0), (5000, 0.5) ... ]`
+    ///
+    /// requires `manual_distribution` feature, ignored otherwise
     pub manual_position_distribution: Option<Vec<(usize, f32)>>,
 
+    /// frequencies that get zeroed as `(center, width)` in hz, bins within `center ± width / 2` are affected
+    ///
+    /// works in frequency domain on magnitudes in `raw_buffer` right after `fft()`,
+    /// so interference tones get removed before any normalisation or interpolation
+    pub suppress_frequencies: Vec<(f32, f32)>,
+
+    /// frequencies in hz that are guaranteed to have a bar after interpolation, useful for labeled gridlines
+    ///
+    /// the regular bar nearest to each pinned frequency gets its `freq` set exactly to it,
+    /// its position moved to where this frequency lies and its volume sampled from the spectrum there,
+    /// so number of bars stays at `resolution`.
+    /// pinned frequencies outside of `frequency_bounds` are ignored
+    pub pinned_frequencies: Vec<f32>,
+
     /// applies positions of frequencies
     pub interpolation: Interpolation,
+
+    /// tension of `Interpolation::Cubic` in range (0..=1)
+    ///
+    /// 0.0 is the full cubic curve, which can overshoot around sharp peaks,
+    /// higher values blend it towards straight lines until 1.0 equals `Interpolation::Linear`
+    pub cubic_tension: f32,
+}
+impl ProcessorConfig {
+    /// sets `frequency_bounds` to range between two notes, see `utils::note_to_freq()` for format
+    ///
+    /// bounds get widened to whole hz, so that both notes are included
+    pub fn set_bounds_notes(&mut self, low: &str, high: &str) -> Result<(), NoteError> {
+        let low = note_to_freq(low)?;
+        let high = note_to_freq(high)?;
+        if low >= high {
+            return Err(NoteError::InvalidRange);
+        }
+        self.frequency_bounds = [low.floor() as usize, high.ceil() as usize];
+        Ok(())
+    }
 }
 impl Default for ProcessorConfig {
     fn default() -> Self {
         ProcessorConfig {
             sampling_rate: 44_100,
             frequency_bounds: [50, 20000],
+            restrict_to_bounds: false,
             resolution: None,
             volume: 1.0,
+            window: Window::Hann,
+            amplitude_mode: AmplitudeMode::Magnitude,
+            real_fft: false,
             volume_normalisation: VolumeNormalisation::Mixture,
-            position_normalisation: PositionNormalisation::Harmonic,
+            volume_scale: VolumeScale::Linear,
+            calibration: None,
+            frequency_axis: FrequencyAxis::Harmonic,
             manual_position_distribution: None,
+            suppress_frequencies: Vec::new(),
+            pinned_frequencies: Vec::new(),
             interpolation: Interpolation::Cubic,
+            cubic_tension: 0.0,
         }
     }
 }
 
+/// what `Stream::update()` does when there is not enough audio for a FFT
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum UnderrunBehavior {
+    /// last frame is kept as it is
+    HoldLast,
+
+    /// volumes of last frame fall by 20% on every starved update
+    DecayToZero,
+
+    /// all volumes are set to 0 immediately
+    Zero,
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct StreamConfig {
     pub channel_count: u16,
     pub processor: ProcessorConfig,
 
+    /// seperate config for every channel, channel `n` uses entry `n`
+    ///
+    /// channels without entry, or all channels if `None`, fall back to `processor`,
+    /// which is also used for everything that is not bound to a single channel, like mid/side processing
+    pub per_channel_processor: Option<Vec<ProcessorConfig>>,
+
     /// with higher resolution comes better precision, that is mostly needed for lower frequencies
     /// at the cost of latency and 'punchiness'
+    ///
+    /// resolutions with a prime factor above 7 get zero-padded, see `Processor::fft()`
     pub fft_resolution: usize,
 
     /// should be set to match fps of output, gravity will be affected, because I have not implemented delta-time
     pub refresh_rate: usize,
 
     pub gravity: Option<f32>,
+
+    /// lowest volume gravity lets frequencies fall to, so that bars keep a minimal resting height
+    ///
+    /// in same units as `volume` of frequencies, so after `processor.volume` got applied, 0.0 lets bars fall to zero.
+    /// falling speed of a frequency only gets reset by a new peak, not by reaching the floor.
+    /// has no effect without `gravity`
+    pub gravity_floor: f32,
+
+    /// normalizes every frequency to its own recent maximum,
+    /// value is the factor by which that maximum decays on each `update()` call, should be in range (0..1)
+    ///
+    /// each frequency region fills its vertical space independently,
+    /// can heavily exaggerate noise in quiet bands
+    pub per_band_agc: Option<f32>,
+
+    /// maximum number of FFTs per second, to limit CPU usage on weak hardware
+    ///
+    /// `update()` calls in between only apply gravity on last frame,
+    /// has no effect without `std` feature
+    pub max_fft_rate: Option<f32>,
+
+    /// maximum rise of volume of every frequency per computed frame
+    ///
+    /// smooths out clicks and sudden transients, while falling is still handled by `gravity`.
+    /// gets applied after `per_band_agc`, so it limits the normalized volume
+    pub max_attack_per_frame: Option<f32>,
+
+    /// keeps positions of frequencies returned by `get_frequencies()` fixed after first frame,
+    /// so that bars only change their height and do not slide sideways
+    ///
+    /// positions only get recomputed when `processor` config or number of frequencies changes,
+    /// so it assumes a stable number of bars, like with a fixed `processor.resolution`
+    pub lock_positions: bool,
+
+    /// keeps full complex FFT of last frame, so that it can be accessed with `Stream::get_complex_spectrum()`
+    ///
+    /// costs `fft_resolution` `Complex<f32>` (8 bytes each) of memory per channel and a copy on every FFT
+    pub retain_complex: bool,
+
+    /// number of threads that compute FFTs of streams with more than two channels in parallel,
+    /// `None` uses all available cores
+    ///
+    /// threads are started on first parallel `update()` and kept until the stream is dropped, so FFT plans are reused.
+    /// requires `parallel` feature, `Some(1)` forces sequential processing
+    pub max_threads: Option<usize>,
+
+    /// maximum amount of audio that `push_data()` keeps buffered per channel,
+    /// bounds latency when `update()` was not called for a while
+    ///
+    /// oldest samples beyond `max_latency * processor.sampling_rate` get dropped,
+    /// but at least `fft_resolution + 1` samples are always kept so that a frame can be computed
+    pub max_latency: Option<Duration>,
+
+    /// behavior of `update()` when not enough audio was pushed for a new frame, see `Stream::is_starved()`
+    pub underrun_behavior: UnderrunBehavior,
+
+    /// crossfades output from old to new `processor` config over this duration when it changes,
+    /// instead of jumping to it
+    ///
+    /// old output is resampled linearly to the number of frequencies of the new one, requires `std` feature
+    pub config_transition: Option<Duration>,
+}
+impl StreamConfig {
+    /// processor config of `channel`, see `per_channel_processor`
+    pub fn processor_for(&self, channel: usize) -> &ProcessorConfig {
+        self.per_channel_processor
+            .as_ref()
+            .and_then(|configs| configs.get(channel))
+            .unwrap_or(&self.processor)
+    }
+
+    /// sets `sampling_rate` of `processor` and of every entry of `per_channel_processor` to `capture_rate`,
+    /// which should be `Capture.sampling_rate` after `init()`
+    ///
+    /// frequencies are off by the ratio of both rates otherwise, like ~9% with 48000 hz audio and the 44100 hz default
+    pub fn sync_sample_rate(&mut self, capture_rate: u32) {
+        self.processor.sampling_rate = capture_rate;
+        if let Some(configs) = self.per_channel_processor.as_mut() {
+            for config in configs.iter_mut() {
+                config.sampling_rate = capture_rate;
+            }
+        }
+    }
 }
 impl Default for StreamConfig {
     fn default() -> Self {
         StreamConfig {
             channel_count: 2,
             processor: ProcessorConfig::default(),
+            per_channel_processor: None,
             fft_resolution: 1024 * 2,
             refresh_rate: 60,
             gravity: Some(1.0),
+            gravity_floor: 0.0,
+            per_band_agc: None,
+            max_fft_rate: None,
+            max_attack_per_frame: None,
+            lock_positions: false,
+            retain_complex: false,
+            max_threads: None,
+            max_latency: None,
+            underrun_behavior: UnderrunBehavior::HoldLast,
+            config_transition: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AnalyzeConfig {
+    /// samples that get processed at once
+    pub window_size: usize,
+
+    /// windows with a RMS below this threshold are skipped
+    ///
+    /// useful to remove leading and trailing silence of recordings
+    pub trim_silence: Option<f32>,
+
+    /// fraction (0..1) by which consecutive windows overlap, 0.5 starts a window every `window_size / 2` samples
+    ///
+    /// every window is processed on its own, so a steady tone has the same volume in every window regardless of overlap.
+    /// only volumes summed over all windows grow with overlap, as every sample is part of multiple windows,
+    /// see `overlap_add`
+    pub overlap: f32,
+
+    /// corrects volumes for summing them over all windows (overlap-add), like for a long-term spectrum
+    ///
+    /// overlapping windows weight every sample by `sum(window) / hop_size` in total, which is their overlap gain.
+    /// for hann windows this is `0.5 / (1 - overlap)`, so 1.0 at 50% and 2.0 at 75% overlap,
+    /// for rectangular windows `1 / (1 - overlap)`.
+    /// volumes of every window get multiplied by the inverse, `hop_size / sum(window)`,
+    /// with the `window` of `ProcessorConfig`, so that sums do not depend on `overlap` and window function anymore,
+    /// but volumes of single windows do
+    pub overlap_add: bool,
+}
+impl Default for AnalyzeConfig {
+    fn default() -> Self {
+        AnalyzeConfig {
+            window_size: 1024 * 2,
+            trim_silence: None,
+            overlap: 0.0,
+            overlap_add: false,
         }
     }
 }
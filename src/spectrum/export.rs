@@ -0,0 +1,37 @@
+//! ## Example
+//! ```
+//! use audioviz::spectrum::{Frequency, export::frames_to_csv};
+//!
+//! let frames: Vec<Vec<Frequency>> = vec![vec![Frequency::empty(); 4]; 2];
+//!
+//! // any `std::io::Write` like `std::fs::File`
+//! let mut csv: Vec<u8> = Vec::new();
+//! frames_to_csv(&frames, &mut csv).unwrap();
+//! ```
+
+use std::io::{self, Write};
+
+use super::Frequency;
+
+/// writes frames as CSV, one row per frame and one column per frequency
+///
+/// first row is a header with the frequency in hz of every column, taken from the first frame,
+/// every following row contains the volumes of one frame.
+/// assumes same number of frequencies in every frame, like it is the case with a fixed `resolution`
+pub fn frames_to_csv<W: Write>(frames: &[Vec<Frequency>], mut writer: W) -> io::Result<()> {
+    let header = match frames.first() {
+        Some(frame) => frame,
+        None => return Ok(()),
+    };
+    write_row(&mut writer, header.iter().map(|f| f.freq))?;
+
+    for frame in frames.iter() {
+        write_row(&mut writer, frame.iter().map(|f| f.volume))?;
+    }
+    writer.flush()
+}
+
+fn write_row<W: Write>(writer: &mut W, values: impl Iterator<Item = f32>) -> io::Result<()> {
+    let row: Vec<String> = values.map(|v| v.to_string()).collect();
+    writeln!(writer, "{}", row.join(","))
+}
@@ -0,0 +1,42 @@
+//! ## Example of DTMF detection
+//! ```
+//! use audioviz::spectrum::goertzel;
+//!
+//! let data: Vec<f32> = vec![0.0; 4096];
+//!
+//! let rows = goertzel::detect(&data, 44_100.0, &[697.0, 770.0, 852.0, 941.0]);
+//! ```
+
+use core::f32::consts::PI;
+
+/// magnitude of every frequency in `targets` using the Goertzel algorithm
+///
+/// much cheaper than a whole FFT when only a few known frequencies are of interest.
+/// magnitude is normalized, so that a sine with amplitude of 1.0 results in roughly 1.0
+///
+/// accuracy depends on length of `data`, a block should contain at least a few periods of the lowest
+/// target, and targets closer together than `sample_rate / data.len()` can not be separated
+pub fn detect(data: &[f32], sample_rate: f32, targets: &[f32]) -> Vec<f32> {
+    if data.is_empty() {
+        return vec![0.0; targets.len()];
+    }
+
+    targets
+        .iter()
+        .map(|target| {
+            let omega: f32 = 2.0 * PI * target / sample_rate;
+            let coeff: f32 = 2.0 * omega.cos();
+
+            let mut s1: f32 = 0.0;
+            let mut s2: f32 = 0.0;
+            for x in data.iter() {
+                let s0 = x + coeff * s1 - s2;
+                s2 = s1;
+                s1 = s0;
+            }
+
+            let power = s1 * s1 + s2 * s2 - coeff * s1 * s2;
+            power.max(0.0).sqrt() * 2.0 / data.len() as f32
+        })
+        .collect()
+}
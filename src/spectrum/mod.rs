@@ -1,23 +1,98 @@
 /// processor of audiodata
 pub mod processor;
 
+/// processor of audiodata with f64 precision
+pub mod processor64;
+
 /// configs for `processor::Processor` and `stream::Stream`
 pub mod config;
 
 /// abstraction over processor with additional effects like gravity
 pub mod stream;
 
+/// worker threads of `stream::Stream`
+#[cfg(feature = "parallel")]
+mod pool;
+
+/// `stream::Stream` for async runtimes
+#[cfg(feature = "async")]
+pub mod async_stream;
+
+/// user defined post-processing of frequencies
+pub mod plugin;
+
+/// statistics over processed frequencies
+pub mod stats;
+
+/// offline analysis of whole recordings
+pub mod offline;
+pub use offline::{analyze, compare};
+
+/// detection of single frequencies without FFT
+pub mod goertzel;
+
+/// pitch detection for tuners
+pub mod pitch;
+
+/// onset detection using spectral flux
+pub mod onset;
+
+/// tempo estimation out of onsets
+pub mod tempo;
+
+/// export of processed frequencies
+pub mod export;
+
+/// binary storage of spectrograms
+pub mod spectrogram;
+pub use spectrogram::Spectrogram;
+
+/// rendering of spectrograms to images
+#[cfg(feature = "image")]
+pub mod render;
+
+use std::iter::Sum;
+
+use crate::fft::FftFloat;
+
+/// float type of `Frequency` and `processor::Processor`, implemented for `f32` and `f64`
+pub trait Float: FftFloat + Sum + Default {
+    /// `x` converted to this type, rounded for `f32`
+    fn cast(x: f64) -> Self;
+
+    fn as_f64(self) -> f64;
+}
+impl Float for f32 {
+    fn cast(x: f64) -> Self {
+        x as f32
+    }
+
+    fn as_f64(self) -> f64 {
+        self as f64
+    }
+}
+impl Float for f64 {
+    fn cast(x: f64) -> Self {
+        x
+    }
+
+    fn as_f64(self) -> f64 {
+        self
+    }
+}
+
 /// Single Frequency
 ///
 /// Multiple of these are stored in a Vector,
+/// precision is `f32` unless a different `Float` is chosen
 #[derive(Clone, Debug)]
-pub struct Frequency {
-    pub volume: f32,
+pub struct Frequency<F = f32> {
+    pub volume: F,
 
     /// Actual frequency in hz, can range from 0 to `config.sampling_rate` / 2
     ///
     /// Accuracy can vary and is not guaranteed
-    pub freq: f32,
+    pub freq: F,
 
     /// Relative position of single frequency in range (0..=1)
     ///
@@ -26,14 +101,66 @@ pub struct Frequency {
     /// Should not be Important, except when distributing freqs manually
     ///
     /// To do this manually set `config.interpolation` equal to `Interpolation::None`
-    pub position: f32,
+    pub position: F,
 }
-impl Frequency {
+impl<F: Float> Frequency<F> {
     pub fn empty() -> Self {
         Frequency {
-            volume: 0.0,
-            freq: 0.0,
-            position: 0.0,
+            volume: F::zero(),
+            freq: F::zero(),
+            position: F::zero(),
         }
     }
 }
+impl From<Frequency<f64>> for Frequency {
+    fn from(freq: Frequency<f64>) -> Self {
+        Frequency {
+            volume: freq.volume as f32,
+            freq: freq.freq as f32,
+            position: freq.position as f32,
+        }
+    }
+}
+impl Frequency {
+
+    /// hue in degrees (0..=360) of `freq`, logarithmically mapped from `min_hz` to `max_hz`
+    ///
+    /// frequencies outside of range get clamped
+    pub fn hue(&self, min_hz: f32, max_hz: f32) -> f32 {
+        let min = min_hz.max(f32::MIN_POSITIVE);
+        let max = max_hz.max(min);
+        if max <= min {
+            return 0.0;
+        }
+        let freq = self.freq.clamp(min, max);
+
+        (freq / min).ln() / (max / min).ln() * 360.0
+    }
+
+    /// fully saturated and bright color of `hue()`
+    pub fn rgb(&self, min_hz: f32, max_hz: f32) -> [u8; 3] {
+        let h = self.hue(min_hz, max_hz) / 60.0;
+        let x = 1.0 - (h % 2.0 - 1.0).abs();
+        let (r, g, b) = match h as u32 {
+            0 => (1.0, x, 0.0),
+            1 => (x, 1.0, 0.0),
+            2 => (0.0, 1.0, x),
+            3 => (0.0, x, 1.0),
+            4 => (x, 0.0, 1.0),
+            5 => (1.0, 0.0, x),
+            _ => (1.0, 0.0, 0.0),
+        };
+        [(r * 255.0).round() as u8, (g * 255.0).round() as u8, (b * 255.0).round() as u8]
+    }
+}
+
+/// maps volume of every frequency to a byte, for example to upload it as texture row
+///
+/// volume gets clamped to (0..=1) and gamma corrected with `volume^(1 / gamma)`,
+/// gamma above 1.0 brightens quiet frequencies, 1.0 is linear
+pub fn frequencies_as_u8(freqs: &[Frequency], gamma: f32) -> Vec<u8> {
+    freqs
+        .iter()
+        .map(|f| (f.volume.clamp(0.0, 1.0).powf(1.0 / gamma) * 255.0).round() as u8)
+        .collect()
+}
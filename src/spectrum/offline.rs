@@ -0,0 +1,84 @@
+//! ## Example
+//! ```
+//! use audioviz::spectrum::{analyze, config::{ProcessorConfig, AnalyzeConfig}};
+//!
+//! // whole recording, for example decoded from a file
+//! let data: Vec<f32> = vec![0.0; 44_100];
+//!
+//! let frames = analyze(&data, &ProcessorConfig::default(), &AnalyzeConfig::default());
+//! for (time, frequencies) in frames {
+//!     println!("{:?}: {}", time, frequencies.len());
+//! }
+//! ```
+
+use std::time::Duration;
+
+use super::config::{AnalyzeConfig, ProcessorConfig};
+use super::{processor::Processor, Frequency};
+use crate::utils::{frame, rms, FrameTail};
+
+/// processes a whole recording window by window, windows can overlap
+///
+/// returns processed frequencies of each window with the time offset of its start,
+/// a tail shorter than `window_size` is ignored,
+/// volumes of a window do not depend on `overlap` unless `overlap_add` is set, see `AnalyzeConfig::overlap_add`
+///
+/// if `trim_silence` is set, windows with a lower RMS are skipped
+pub fn analyze(data: &[f32], config: &ProcessorConfig, analyze_config: &AnalyzeConfig) -> Vec<(Duration, Vec<Frequency>)> {
+    let window_size: usize = analyze_config.window_size.max(1);
+    let overlap: f32 = analyze_config.overlap.clamp(0.0, 0.99);
+    let hop_size: usize = ((window_size as f32 * (1.0 - overlap)).round() as usize).max(1);
+    // inverse of overlap gain of window function
+    let correction: Option<f32> = if analyze_config.overlap_add {
+        let window_sum: f64 = config.window.coefficients_f64(window_size).iter().sum();
+        Some((hop_size as f64 / window_sum.max(f64::MIN_POSITIVE)) as f32)
+    } else {
+        None
+    };
+
+    let mut frames: Vec<(Duration, Vec<Frequency>)> = Vec::new();
+    for (i, window) in frame(data, window_size, hop_size, FrameTail::Drop).iter().enumerate() {
+        if let Some(threshold) = analyze_config.trim_silence {
+            if rms(window) < threshold {
+                continue;
+            }
+        }
+
+        let offset = Duration::from_secs_f64((i * hop_size) as f64 / config.sampling_rate as f64);
+
+        let mut processor = Processor::from_raw_data(config.clone(), window.to_vec());
+        processor.compute_all();
+        if let Some(correction) = correction {
+            for freq in processor.freq_buffer.iter_mut() {
+                freq.volume *= correction;
+            }
+        }
+        frames.push((offset, processor.freq_buffer));
+    }
+
+    frames
+}
+
+/// processes identical `samples` with both configs and pairs up resulting frequencies by index
+///
+/// useful for A/B testing of configs, if outputs differ in length,
+/// the shorter one gets padded with `Frequency::empty()` at the end
+pub fn compare(samples: &[f32], a: &ProcessorConfig, b: &ProcessorConfig) -> Vec<(Frequency, Frequency)> {
+    let process = |config: &ProcessorConfig| -> Vec<Frequency> {
+        let mut processor = Processor::from_raw_data(config.clone(), samples.to_vec());
+        processor.compute_all();
+        processor.freq_buffer
+    };
+    let a = process(a);
+    let b = process(b);
+
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| {
+            (
+                a.get(i).cloned().unwrap_or_else(Frequency::empty),
+                b.get(i).cloned().unwrap_or_else(Frequency::empty),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,146 @@
+//! ## Example
+//! ```
+//! use audioviz::spectrum::{onset::OnsetDetector, Frequency};
+//!
+//! let mut detector = OnsetDetector::default();
+//!
+//! let silence = vec![Frequency::empty(); 16];
+//! let loud: Vec<Frequency> = (0..16).map(|_| Frequency { volume: 1.0, ..Frequency::empty() }).collect();
+//!
+//! assert!(!detector.push(&silence));
+//! assert!(detector.push(&loud));
+//! ```
+
+use std::collections::VecDeque;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+use super::Frequency;
+
+// number of past flux values the adaptive threshold is based on
+const FLUX_HISTORY: usize = 16;
+
+// number of inter-onset intervals that are kept
+#[cfg(feature = "std")]
+const INTERVAL_HISTORY: usize = 16;
+
+/// detects onsets in processed frequencies using spectral flux
+///
+/// flux is the sum of all volume increases compared to the previous frame,
+/// an onset is detected if flux exceeds `threshold` times the mean flux of the last 16 frames
+/// and the previous frame was not an onset.
+///
+/// with `std` feature the time of every onset is tracked,
+/// intervals are measured between timestamps of successive detected onsets
+#[derive(Clone, Debug)]
+pub struct OnsetDetector {
+    pub threshold: f32,
+    previous: Option<Vec<f32>>,
+    flux: VecDeque<f32>,
+    was_onset: bool,
+    #[cfg(feature = "std")]
+    last_onset: Option<Instant>,
+    #[cfg(feature = "std")]
+    intervals: VecDeque<Duration>,
+}
+impl Default for OnsetDetector {
+    fn default() -> Self {
+        Self::new(1.5)
+    }
+}
+impl OnsetDetector {
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            previous: None,
+            flux: VecDeque::with_capacity(FLUX_HISTORY),
+            was_onset: false,
+            #[cfg(feature = "std")]
+            last_onset: None,
+            #[cfg(feature = "std")]
+            intervals: VecDeque::with_capacity(INTERVAL_HISTORY),
+        }
+    }
+
+    /// feeds next frame of frequencies, returns true if it is an onset
+    ///
+    /// frames should have the same length, otherwise flux is only computed over the shorter one
+    #[cfg(feature = "std")]
+    pub fn push(&mut self, frame: &[Frequency]) -> bool {
+        self.push_at(frame, Instant::now())
+    }
+
+    /// same as `push()` but without tracking time
+    #[cfg(not(feature = "std"))]
+    pub fn push(&mut self, frame: &[Frequency]) -> bool {
+        self.detect(frame)
+    }
+
+    /// same as `push()` but with time of frame given as `now`
+    #[cfg(feature = "std")]
+    pub fn push_at(&mut self, frame: &[Frequency], now: Instant) -> bool {
+        let onset = self.detect(frame);
+        if onset {
+            if let Some(last) = self.last_onset {
+                if self.intervals.len() >= INTERVAL_HISTORY {
+                    self.intervals.pop_front();
+                }
+                self.intervals.push_back(now.saturating_duration_since(last));
+            }
+            self.last_onset = Some(now);
+        }
+        onset
+    }
+
+    /// time between the last two detected onsets
+    #[cfg(feature = "std")]
+    pub fn last_interval(&self) -> Option<Duration> {
+        self.intervals.back().copied()
+    }
+
+    /// up to 16 most recent inter-onset intervals, oldest first
+    #[cfg(feature = "std")]
+    pub fn intervals(&self) -> &VecDeque<Duration> {
+        &self.intervals
+    }
+
+    pub fn clear(&mut self) {
+        self.previous = None;
+        self.flux.clear();
+        self.was_onset = false;
+        #[cfg(feature = "std")]
+        {
+            self.last_onset = None;
+            self.intervals.clear();
+        }
+    }
+
+    fn detect(&mut self, frame: &[Frequency]) -> bool {
+        let volumes: Vec<f32> = frame.iter().map(|f| f.volume).collect();
+        let previous = match self.previous.replace(volumes) {
+            Some(previous) => previous,
+            None => return false,
+        };
+        let current = self.previous.as_ref().unwrap();
+
+        let flux: f32 = current
+            .iter()
+            .zip(previous.iter())
+            .map(|(c, p)| (c - p).max(0.0))
+            .sum();
+
+        let mean: f32 = if self.flux.is_empty() {
+            0.0
+        } else {
+            self.flux.iter().sum::<f32>() / self.flux.len() as f32
+        };
+        if self.flux.len() >= FLUX_HISTORY {
+            self.flux.pop_front();
+        }
+        self.flux.push_back(flux);
+
+        let onset = flux > f32::EPSILON && flux > mean * self.threshold && !self.was_onset;
+        self.was_onset = onset;
+        onset
+    }
+}
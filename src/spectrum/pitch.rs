@@ -0,0 +1,147 @@
+//! ## Example of tuner
+//! ```
+//! use audioviz::spectrum::pitch::PitchTracker;
+//!
+//! let mut tracker = PitchTracker::new(44_100.0, 5);
+//!
+//! // frames of raw audio data, for example from `distributor`
+//! let frame: Vec<f32> = (0..4096).map(|i| (i as f32 * 0.0627).sin()).collect();
+//! tracker.push(&frame);
+//!
+//! if let Some(pitch) = tracker.pitch_hz() {
+//!     // how far off A4 (midi note 69) it is
+//!     println!("{}hz, {} cents", pitch, tracker.cents_off(69.0).unwrap());
+//! }
+//! ```
+
+use std::collections::VecDeque;
+
+use crate::{fft, utils::{apodize, freq_to_note}};
+
+// highest harmonic that is considered for octave error check
+const HARMONICS: usize = 4;
+
+// magnitude a sub-harmonic needs relative to strongest peak, to be considered as fundamental
+const SUBHARMONIC_THRESHOLD: f32 = 0.2;
+
+/// frequency of strongest bin in `magnitudes`, with sub-bin accuracy
+///
+/// `magnitudes` must be the non-mirrored magnitude spectrum of a hann windowed frame,
+/// as returned by `fft::remove_mirroring()`
+///
+/// peak is refined by fitting a parabola through the logarithmic magnitudes of it and its neighbours
+pub fn refined_peak_frequency(magnitudes: &[f32], sample_rate: f32) -> Option<f32> {
+    let peak = peak_bin(magnitudes, 0, magnitudes.len())?;
+    Some(refine(magnitudes, peak, bin_width(magnitudes, sample_rate)))
+}
+
+/// tracks pitch over multiple frames for stable tuner readings
+///
+/// each frame is checked for octave errors: if a sub-harmonic of the strongest peak is present as well
+/// it is taken as fundamental
+///
+/// result is median of the last `smoothing` frames, which delays changes of pitch by about `smoothing / 2` frames
+#[derive(Clone, Debug)]
+pub struct PitchTracker {
+    sample_rate: f32,
+    smoothing: usize,
+    history: VecDeque<f32>,
+}
+impl PitchTracker {
+    pub fn new(sample_rate: f32, smoothing: usize) -> Self {
+        let smoothing = smoothing.max(1);
+        Self {
+            sample_rate,
+            smoothing,
+            history: VecDeque::with_capacity(smoothing),
+        }
+    }
+
+    /// processes single frame of raw audio data, silent frames are ignored
+    pub fn push(&mut self, frame: &[f32]) {
+        let mut data = frame.to_vec();
+        apodize(&mut data);
+        let magnitudes = fft::remove_mirroring(&fft::normalize(&fft::forward(&data)));
+
+        let peak = match peak_bin(&magnitudes, 0, magnitudes.len()) {
+            Some(p) => p,
+            None => return,
+        };
+        if magnitudes[peak] <= f32::EPSILON {
+            return;
+        }
+
+        // octave error check, lowest present sub-harmonic is fundamental
+        let mut fundamental = peak;
+        for harmonic in (2..=HARMONICS).rev() {
+            let expected = peak / harmonic;
+            if expected < 2 {
+                continue;
+            }
+            if let Some(candidate) = peak_bin(&magnitudes, expected - 1, expected + 2) {
+                if magnitudes[candidate] >= magnitudes[peak] * SUBHARMONIC_THRESHOLD {
+                    fundamental = candidate;
+                    break;
+                }
+            }
+        }
+
+        let pitch = refine(&magnitudes, fundamental, bin_width(&magnitudes, self.sample_rate));
+
+        if self.history.len() >= self.smoothing {
+            self.history.pop_front();
+        }
+        self.history.push_back(pitch);
+    }
+
+    /// median pitch of recent frames
+    pub fn pitch_hz(&self) -> Option<f32> {
+        if self.history.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<f32> = self.history.iter().cloned().collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        Some(sorted[sorted.len() / 2])
+    }
+
+    /// deviation of pitch from `reference_note` (midi note number, A4 = 69) in cents
+    pub fn cents_off(&self, reference_note: f32) -> Option<f32> {
+        self.pitch_hz().map(|pitch| (freq_to_note(pitch) - reference_note) * 100.0)
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+}
+
+fn bin_width(magnitudes: &[f32], sample_rate: f32) -> f32 {
+    let fft_len = (magnitudes.len().max(2) - 1) * 2;
+    sample_rate / fft_len as f32
+}
+
+// strongest bin in range, ignores DC
+fn peak_bin(magnitudes: &[f32], start: usize, end: usize) -> Option<usize> {
+    let end = end.min(magnitudes.len());
+    (start.max(1)..end).fold(None, |max: Option<usize>, i| match max {
+        Some(m) if magnitudes[m] >= magnitudes[i] => Some(m),
+        _ => Some(i),
+    })
+}
+
+fn refine(magnitudes: &[f32], bin: usize, bin_width: f32) -> f32 {
+    if bin == 0 || bin + 1 >= magnitudes.len() {
+        return bin as f32 * bin_width;
+    }
+    let ln = |x: f32| x.max(f32::MIN_POSITIVE).ln();
+    let (a, b, c) = (ln(magnitudes[bin - 1]), ln(magnitudes[bin]), ln(magnitudes[bin + 1]));
+
+    let denominator = a - 2.0 * b + c;
+    let offset = if denominator.abs() > f32::EPSILON {
+        (0.5 * (a - c) / denominator).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+
+    (bin as f32 + offset) * bin_width
+}
@@ -0,0 +1,32 @@
+//! ## Example of custom plugin
+//! ```
+//! use audioviz::spectrum::{Frequency, config::ProcessorConfig, plugin::SpectrumPlugin, processor::Processor};
+//!
+//! #[derive(Debug)]
+//! struct Amplifier(f32);
+//!
+//! impl SpectrumPlugin for Amplifier {
+//!     fn apply(&self, freqs: &mut Vec<Frequency>, _config: &ProcessorConfig) {
+//!         for freq in freqs.iter_mut() {
+//!             freq.volume *= self.0;
+//!         }
+//!     }
+//! }
+//!
+//! let data = vec![0.0, 1.0, 0.0, 0.5, -1.0, 0.043];
+//! let mut processor = Processor::from_raw_data(ProcessorConfig::default(), data);
+//! processor.add_plugin(Box::new(Amplifier(2.0)));
+//! processor.compute_all();
+//! ```
+
+use std::fmt::Debug;
+
+use super::{config::ProcessorConfig, Frequency};
+
+/// custom post-processing step of `processor::Processor`
+///
+/// gets applied on `freq_buffer` after positions are normalized and before it gets bound and interpolated
+pub trait SpectrumPlugin<F = f32>: Debug + Send + Sync {
+    #[allow(clippy::ptr_arg)]
+    fn apply(&self, freqs: &mut Vec<Frequency<F>>, config: &ProcessorConfig);
+}
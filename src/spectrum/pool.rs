@@ -0,0 +1,111 @@
+//! persistent worker threads that compute FFTs of `stream::Stream` channels in parallel
+//!
+//! threads live as long as the pool, so FFT plans that `fft` caches per thread are reused on every frame
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::JoinHandle;
+
+use super::config::ProcessorConfig;
+use super::stream::{compute_spectrum, Spectrum};
+
+// channel index, config and raw data of channel, retain complex
+type Job = (usize, ProcessorConfig, Vec<f32>, bool);
+
+pub(crate) struct Pool {
+    threads: usize,
+    jobs: Option<Sender<Job>>,
+    results: Receiver<(usize, Spectrum)>,
+    handles: Vec<JoinHandle<()>>,
+
+    // plans created on all worker threads, to verify that they are reused
+    #[cfg(test)]
+    plans_created: Arc<AtomicUsize>,
+}
+impl Pool {
+    pub(crate) fn new(threads: usize) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (result_sender, results) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        #[cfg(test)]
+        let plans_created = Arc::new(AtomicUsize::new(0));
+
+        let handles = (0..threads)
+            .map(|_| {
+                let job_receiver = job_receiver.clone();
+                let result_sender = result_sender.clone();
+                #[cfg(test)]
+                let plans_created = plans_created.clone();
+                std::thread::spawn(move || loop {
+                    // lock is released before the job is computed
+                    let job = job_receiver.lock().map_err(|_| ()).and_then(|r| r.recv().map_err(|_| ()));
+                    let (channel, config, raw_data, retain_complex) = match job {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    #[cfg(test)]
+                    let before = crate::fft::plans_created();
+                    let spectrum = compute_spectrum(&config, &raw_data, retain_complex);
+                    #[cfg(test)]
+                    plans_created.fetch_add(crate::fft::plans_created() - before, Ordering::SeqCst);
+                    if result_sender.send((channel, spectrum)).is_err() {
+                        break;
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            threads,
+            jobs: Some(jobs),
+            results,
+            handles,
+            #[cfg(test)]
+            plans_created,
+        }
+    }
+
+    pub(crate) fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// computes spectrum of every job, results are ordered like `jobs`
+    pub(crate) fn compute(&self, jobs: Vec<(ProcessorConfig, Vec<f32>, bool)>) -> Vec<Spectrum> {
+        let sender = match &self.jobs {
+            Some(sender) => sender,
+            None => return Vec::new(),
+        };
+        let count = jobs.len();
+        for (channel, (config, raw_data, retain_complex)) in jobs.into_iter().enumerate() {
+            if sender.send((channel, config, raw_data, retain_complex)).is_err() {
+                log::warn!("fft worker thread exited");
+                return Vec::new();
+            }
+        }
+
+        let mut spectra: Vec<Option<Spectrum>> = (0..count).map(|_| None).collect();
+        for _ in 0..count {
+            match self.results.recv() {
+                Ok((channel, spectrum)) => spectra[channel] = Some(spectrum),
+                Err(_) => break,
+            }
+        }
+        spectra.into_iter().map(|s| s.unwrap_or_default()).collect()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn plans_created(&self) -> usize {
+        self.plans_created.load(Ordering::SeqCst)
+    }
+}
+impl Drop for Pool {
+    fn drop(&mut self) {
+        // closing the channel lets every worker leave its loop
+        self.jobs = None;
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
@@ -18,111 +18,404 @@
 //! println!("{:#?}", frequencies);
 //! ```
 
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "manual_distribution")]
 use splines::{Interpolation, Key, Spline};
 
 use crate::spectrum::config::Interpolation as ConfigInterpolation;
-use crate::spectrum::config::{ProcessorConfig, VolumeNormalisation, PositionNormalisation};
-use crate::{fft, utils::apodize};
+use crate::spectrum::config::{AmplitudeMode, ProcessorConfig, VolumeNormalisation, VolumeScale, FrequencyAxis, Window, MIN_GAMMA};
+use crate::{fft::{self, Complex}, utils::freq_to_note};
+
+use crate::spectrum::{Float, Frequency};
+use crate::spectrum::plugin::SpectrumPlugin;
+
+// warning about zero-padding of unusual fft lengths is only printed once
+pub(super) static PADDING_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// what the last `interpolate()` call of a `Processor` did
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterpolationInfo {
+    pub mode: ConfigInterpolation,
+
+    /// length of `freq_buffer` before interpolation
+    pub input_len: usize,
+
+    /// length of `freq_buffer` after interpolation
+    pub output_len: usize,
+}
+
+/// time spent in each stage of `Processor::compute_all_timed()`
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StageTimings {
+    pub apodize: Duration,
+
+    /// `fft()` and `suppress_frequencies()`
+    pub fft: Duration,
+
+    /// everything between fft and interpolation: volume and position normalisation, plugins and bounds
+    pub normalize: Duration,
 
-use crate::spectrum::Frequency;
+    pub interpolate: Duration,
+
+    /// whole pipeline, slightly more than the sum of all stages
+    pub total: Duration,
+}
 
 /// struct that deals with processing for spectralized output with the help of Fast Fourier Transform
+///
+/// processes in `f32` unless a different `Float` is chosen, see `processor64::Processor64`
 #[derive(Clone, Debug)]
-pub struct Processor {
+pub struct Processor<F: Float = f32> {
     config: ProcessorConfig,
-    pub raw_buffer: Vec<f32>,
-    pub freq_buffer: Vec<Frequency>,
+    pub raw_buffer: Vec<F>,
+    pub freq_buffer: Vec<Frequency<F>>,
+    plugins: Vec<Arc<dyn SpectrumPlugin<F>>>,
+    last_interpolation: Option<InterpolationInfo>,
+    dc: F,
+    nyquist: F,
+    // number of FFT bins `freq_buffer` was created from in `raw_to_freq_buffer()`, and index of its first bin
+    bin_count: usize,
+    bin_offset: usize,
+    // magnitudes right after `fft()`
+    bins: Vec<F>,
+    // mean of window of last `apodize()` call, and magnitude of a full scale sine after last `fft()`
+    window_gain: F,
+    full_scale: F,
 }
 
-impl Processor {
-    pub fn from_raw_data(config: ProcessorConfig, data: Vec<f32>) -> Self {
+impl<F: Float> Processor<F> {
+    pub fn from_raw_data(config: ProcessorConfig, data: Vec<F>) -> Self {
         let freq_buf_cap: usize = data.len() / 2;
         Processor {
             config,
             raw_buffer: data,
             freq_buffer: Vec::with_capacity(freq_buf_cap),
+            plugins: Vec::new(),
+            last_interpolation: None,
+            dc: F::zero(),
+            nyquist: F::zero(),
+            bin_count: 0,
+            bin_offset: 0,
+            bins: Vec::new(),
+            window_gain: F::one(),
+            full_scale: F::zero(),
         }
     }
-    pub fn from_frequencies(config: ProcessorConfig, freqs: Vec<Frequency>) -> Self {
+    pub fn from_frequencies(config: ProcessorConfig, freqs: Vec<Frequency<F>>) -> Self {
         Processor {
             config,
             raw_buffer: Vec::new(),
             freq_buffer: freqs,
+            plugins: Vec::new(),
+            last_interpolation: None,
+            dc: F::zero(),
+            nyquist: F::zero(),
+            bin_count: 0,
+            bin_offset: 0,
+            bins: Vec::new(),
+            window_gain: F::one(),
+            full_scale: F::zero(),
         }
     }
 
+    pub fn config(&self) -> &ProcessorConfig {
+        &self.config
+    }
+
+    /// calibrates volume with `raw_buffer`, which has to contain a sine of `reference_sine_amplitude`
+    ///
+    /// procedure: create a processor out of a recorded or generated reference sine,
+    /// call this before any other processing and reuse `config()` for all following processors,
+    /// a sine of amplitude 1.0 at the reference frequency then reads as volume 1.0, whatever `fft_resolution` is.
+    /// volume normalisation depends on frequency and slightly on `fft_resolution`,
+    /// so readings are only exact for all frequencies and resolutions with `VolumeNormalisation::None`.
+    ///
+    /// sets `config.calibration` and returns it, `None` if `raw_buffer` is silent
+    pub fn calibrate(&mut self, reference_sine_amplitude: f32) -> Option<f32> {
+        let mut reference = Processor::from_raw_data(self.config.clone(), self.raw_buffer.clone());
+        reference.apodize();
+        reference.fft();
+        reference.normalize_frequency_volume();
+
+        // dc is not part of a sine
+        let peak: F = reference.raw_buffer.iter().skip(1).fold(F::zero(), |max, v| max.max(*v));
+        if peak <= F::zero() || reference.full_scale <= F::zero() {
+            return None;
+        }
+        let calibration: f32 = (F::cast(reference_sine_amplitude as f64) / (peak / reference.full_scale)).as_f64() as f32;
+        self.config.calibration = Some(calibration);
+        Some(calibration)
+    }
+
+    /// registers plugin that gets applied in `compute_all()`
+    ///
+    /// plugins are applied in the order they were added
+    pub fn add_plugin(&mut self, plugin: Box<dyn SpectrumPlugin<F>>) {
+        self.plugins.push(Arc::from(plugin));
+    }
+
     /// processes everything in recommended order
     pub fn compute_all(&mut self) {
+        self.compute_stages(|| ());
+    }
+
+    /// same as `compute_all()`, but measures how long each stage takes
+    ///
+    /// measuring adds minor overhead, so it is meant for profiling of configs like `fft_resolution` or interpolation
+    #[cfg(feature = "std")]
+    pub fn compute_all_timed(&mut self) -> StageTimings {
+        let start = Instant::now();
+        let mut ends: Vec<Instant> = Vec::with_capacity(4);
+        self.compute_stages(|| ends.push(Instant::now()));
+
+        let stage = |i: usize| ends[i] - if i == 0 { start } else { ends[i - 1] };
+        StageTimings {
+            apodize: stage(0),
+            fft: stage(1),
+            normalize: stage(2),
+            interpolate: stage(3),
+            total: start.elapsed(),
+        }
+    }
+
+    // pipeline of `compute_all()`, `stage_done` is called after apodization, fft, normalisation and interpolation
+    fn compute_stages(&mut self, mut stage_done: impl FnMut()) {
         self.apodize();
+        stage_done();
+
         self.fft();
-        self.normalize_frequency_volume();
+        self.suppress_frequencies();
+        stage_done();
 
+        self.normalize_frequency_volume();
         self.raw_to_freq_buffer();
         self.normalize_frequency_position();
         self.distribute_frequency_position();
+        self.apply_plugins();
         self.bound_frequencies();
+        stage_done();
+
         self.interpolate();
+        stage_done();
+    }
+
+    /// applies all registered plugins on `freq_buffer`
+    pub fn apply_plugins(&mut self) {
+        for plugin in self.plugins.iter() {
+            plugin.apply(&mut self.freq_buffer, &self.config);
+        }
     }
 
 
-    /// applies hanning windowing to `raw_buffer`
+    /// applies window function of config to `raw_buffer`, hanning by default
     /// 
     /// this removes noise
     pub fn apodize(&mut self) {
-        apodize(&mut self.raw_buffer)
+        let len = self.raw_buffer.len();
+        self.window_gain = match self.config.window {
+            Window::Rectangular => F::one(),
+            ref window => {
+                let coefficients: Vec<F> = window.coefficients_f64(len).into_iter().map(F::cast).collect();
+                for (x, w) in self.raw_buffer.iter_mut().zip(coefficients.iter()) {
+                    *x = *x * *w;
+                }
+                match window {
+                    // same as before `window` was configurable
+                    Window::Hann if len > 1 => F::cast((len - 1) as f64) / F::cast((2 * len) as f64),
+                    _ => coefficients.iter().copied().sum::<F>() / F::cast(len.max(1) as f64),
+                }
+            }
+        };
     }
 
     /// processes fft algorithm on `raw_buffer`
+    ///
+    /// lengths with a prime factor above 7 (primes like 1021 for example) make fft planning slow,
+    /// so `raw_buffer` gets zero-padded to the next length made only of factors 2, 3, 5 and 7,
+    /// which results in `next_len / 2 + 1` bins instead of `len / 2 + 1`
     pub fn fft(&mut self) {
-        let fft = fft::forward(&self.raw_buffer);
-        let fft = fft::normalize(&fft);
-        let fft = fft::remove_mirroring(&fft);
-        self.raw_buffer = fft;
+        self.fft_with(false);
+    }
+
+    /// same as `fft()`, but returns full complex spectrum including its mirrored half
+    pub(crate) fn fft_complex(&mut self) -> Vec<Complex<F>> {
+        self.fft_with(true).unwrap_or_default()
+    }
+
+    fn fft_with(&mut self, retain_complex: bool) -> Option<Vec<Complex<F>>> {
+        let len = self.raw_buffer.len();
+        let fft_len = fft::next_smooth_len(len);
+        if fft_len != len {
+            if !PADDING_WARNED.swap(true, Ordering::Relaxed) {
+                log::warn!("fft length of {} is slow to plan, zero-padding to {}", len, fft_len);
+            }
+            self.raw_buffer.resize(fft_len, F::zero());
+        }
+
+        // only lower half of spectrum is relevant for real input
+        let mut complex = None;
+        let fft = if retain_complex {
+            let full = fft::forward(&self.raw_buffer);
+            let half = fft::remove_mirroring(&full);
+            complex = Some(full);
+            half
+        } else if self.config.real_fft {
+            fft::forward_real(&self.raw_buffer)
+        } else {
+            fft::remove_mirroring(&fft::forward(&self.raw_buffer))
+        };
+
+        if len > 0 {
+            // normalized by unpadded length, padding does not change sums
+            self.dc = fft[0].re / F::cast(len as f64);
+            // only even lengths have a nyquist bin
+            self.nyquist = if fft_len.is_multiple_of(2) { fft[fft_len / 2].re / F::cast(len as f64) } else { F::zero() };
+        }
+
+        self.raw_buffer = fft::normalize(&fft);
+        self.apply_amplitude_mode();
+        self.bins = self.raw_buffer.clone();
+        self.full_scale = F::cast(self.config.amplitude_mode.apply((len as f64 * self.window_gain.as_f64()) / 2.0));
+
+        complex
+    }
+
+    /// magnitude of FFT bin `index` of last `fft()` call, `None` if out of range
+    ///
+    /// reflects windowing and `amplitude_mode`, but no suppression, volume or position normalisation
+    pub fn raw_bin(&self, index: usize) -> Option<F> {
+        self.bins.get(index).copied()
+    }
+
+    /// magnitudes of all `len / 2 + 1` FFT bins of last `fft()` call, see `raw_bin()`
+    pub fn raw_bins(&self) -> &[F] {
+        &self.bins
+    }
+
+    // maps magnitudes in `raw_buffer` according to `amplitude_mode` of config
+    fn apply_amplitude_mode(&mut self) {
+        if self.config.amplitude_mode == AmplitudeMode::Magnitude {
+            return;
+        }
+        for x in self.raw_buffer.iter_mut() {
+            *x = F::cast(self.config.amplitude_mode.apply(x.as_f64()));
+        }
+    }
+
+    /// zeroes bins of `raw_buffer` that lie in `suppress_frequencies` of config
+    ///
+    /// must be called right after `fft()`,
+    /// bin `i` is at `i * sampling_rate / fft_size` hz
+    pub fn suppress_frequencies(&mut self) {
+        let len = self.raw_buffer.len();
+        if len < 2 || self.config.suppress_frequencies.is_empty() {
+            return;
+        }
+        let two = F::cast(2.0);
+        let bin_width = F::cast(self.config.sampling_rate as f64) / two / F::cast((len - 1) as f64);
+
+        for (center, width) in self.config.suppress_frequencies.iter() {
+            let (center, width) = (F::cast(*center as f64), F::cast(*width as f64));
+            let low = as_index(((center - width / two) / bin_width).ceil().max(F::zero()));
+            let high = ((center + width / two) / bin_width).floor();
+            if high < F::zero() {
+                continue;
+            }
+            let high = as_index(high).min(len - 1);
+            for bin in self.raw_buffer.iter_mut().take(high + 1).skip(low) {
+                *bin = F::zero();
+            }
+        }
+    }
+
+    /// DC component of the last `fft()` call, taken from raw complex spectrum
+    ///
+    /// equals mean of `raw_buffer` at time of fft,
+    /// so after `apodize()` it reflects residual offset after windowing,
+    /// independent of `freq_buffer` and any normalisation
+    pub fn dc_component(&self) -> F {
+        self.dc
+    }
+
+    /// nyquist component of the last `fft()` call, taken from raw complex spectrum
+    ///
+    /// is signed and normalized by length like `dc_component()`, 0 for odd fft lengths
+    pub fn nyquist_component(&self) -> F {
+        self.nyquist
+    }
+
+    // bins of `raw_buffer` that get normalised and converted, all of them unless `restrict_to_bounds` is set
+    //
+    // includes two bins of margin on both sides, `bound_frequencies()` trims them afterwards
+    fn bin_range(&self) -> Range<usize> {
+        let len = self.raw_buffer.len();
+        if !self.config.restrict_to_bounds || self.config.manual_position_distribution.is_some() || len == 0 {
+            return 0..len;
+        }
+        // bin `i` is at `(i + 1) * bin_width` hz, see `raw_to_freq_buffer()`
+        let bin_width = F::cast(self.config.sampling_rate as f64) / F::cast(2.0) / F::cast(len as f64);
+        let end = (as_index((F::cast(self.config.frequency_bounds[1] as f64) / bin_width).ceil()) + 1).min(len);
+        let start = as_index((F::cast(self.config.frequency_bounds[0] as f64) / bin_width).floor())
+            .saturating_sub(2)
+            .min(end);
+
+        start..end
     }
 
     /// normalizes volume on `raw_buffer` so that higher frequencies are louder
     pub fn normalize_frequency_volume(&mut self) {
+        let (one, two) = (F::one(), F::cast(2.0));
         match &self.config.volume_normalisation {
             VolumeNormalisation::None => (),
             VolumeNormalisation::Exponential => {
-                for i in 0..self.raw_buffer.len() {
-                    let percentage = (i + 1) as f32 / self.raw_buffer.len() as f32;
-                    self.raw_buffer[i] *= percentage.sqrt();
+                for i in self.bin_range() {
+                    let percentage = F::cast((i + 1) as f64) / F::cast(self.raw_buffer.len() as f64);
+                    self.raw_buffer[i] = self.raw_buffer[i] * percentage.sqrt();
                 }
             }
             VolumeNormalisation::Logarithmic => {
-                for i in 0..self.raw_buffer.len() {
-                    let percentage = (i + 1) as f32 / self.raw_buffer.len() as f32;
-                    self.raw_buffer[i] *= 1.0 / 2_f32.log(percentage + 1.0);
+                for i in self.bin_range() {
+                    let percentage = F::cast((i + 1) as f64) / F::cast(self.raw_buffer.len() as f64);
+                    self.raw_buffer[i] = self.raw_buffer[i] * (one / two.log(percentage + one));
                 }
             }
             VolumeNormalisation::Mixture => {
-                for i in 0..self.raw_buffer.len() {
-                    let percentage = (i + 1) as f32 / self.raw_buffer.len() as f32;
-                    let log: f32 = 1.0 / 2_f32.log(percentage + 1.0);
-                    let exp: f32 = percentage.sqrt();
-                    self.raw_buffer[i] *= (log + exp) / 2.0;
+                for i in self.bin_range() {
+                    let percentage = F::cast((i + 1) as f64) / F::cast(self.raw_buffer.len() as f64);
+                    let log: F = one / two.log(percentage + one);
+                    let exp: F = percentage.sqrt();
+                    self.raw_buffer[i] = self.raw_buffer[i] * ((log + exp) / two);
                 } 
             }
         }
     }
 
     /// manual position distribution on `freq_buffer`
+    ///
+    /// no-op without `manual_distribution` feature
     pub fn distribute_frequency_position(&mut self) {
+        #[cfg(feature = "manual_distribution")]
         if let Some(distribution) = &self.config.manual_position_distribution {
             let dis_spline = get_dis_spline(distribution);
 
             let freq_buf_len: usize = self.freq_buffer.len();
-            let mut last_position: f32 = 0.0;
-            let mut pointer_pos: f32 = 0.0;
+            let mut last_position: F = F::zero();
+            let mut pointer_pos: F = F::zero();
             for (i, val) in self.freq_buffer.iter_mut().enumerate() {
-                let percentage: f32 = (i + 1) as f32 / freq_buf_len as f32;
-                let freq: f32 = percentage * (self.config.sampling_rate as f32 / 2.0);
-                let offset = dis_spline.clamped_sample(freq).unwrap_or(1.0);
+                let percentage: F = F::cast((i + 1) as f64) / F::cast(freq_buf_len as f64);
+                let freq: F = percentage * (F::cast(self.config.sampling_rate as f64) / F::cast(2.0));
+                // spline is sampled in f32 like the distribution of config
+                let offset = F::cast(dis_spline.clamped_sample(freq.as_f64() as f32).unwrap_or(1.0) as f64);
 
                 let diff = val.position - last_position;
 
-                pointer_pos += diff * offset;
+                pointer_pos = pointer_pos + diff * offset;
                 last_position = val.position;
                 val.position = pointer_pos;
             }
@@ -130,10 +423,11 @@ impl Processor {
             // makes sure that position of every frequency is <= 1.0
             let max_pos = self.freq_buffer[self.freq_buffer.len() - 1].position;
             for freq in self.freq_buffer.iter_mut() {
-                freq.position /= max_pos;
+                freq.position = freq.position / max_pos;
             }
         }
 
+        #[cfg(feature = "manual_distribution")]
         #[allow(clippy::ptr_arg)]
         fn get_dis_spline(distribution: &Vec<(usize, f32)>) -> Spline<f32, f32> {
             let mut points: Vec<Key<f32, f32>> = Vec::new();
@@ -150,58 +444,111 @@ impl Processor {
 
     /// populates the `freq_buffer` and applies volume
     pub fn raw_to_freq_buffer(&mut self) {
-        for (i, val) in self.raw_buffer.iter().enumerate() {
-            let percentage: f32 = (i + 1) as f32 / self.raw_buffer.len() as f32;
+        self.bin_count = self.raw_buffer.len();
+        let range = self.bin_range();
+        self.bin_offset = range.start;
+        let full_scale: F = if self.full_scale > F::zero() { self.full_scale } else { F::one() };
+        let calibration: Option<F> = self.config.calibration.map(|c| F::cast(c as f64));
+        for (i, val) in self.raw_buffer.iter().enumerate().take(range.end).skip(range.start) {
+            let percentage: F = F::cast((i + 1) as f64) / F::cast(self.raw_buffer.len() as f64);
+            let volume: F = match (&self.config.volume_scale, calibration) {
+                (VolumeScale::Linear, None) => *val,
+                (VolumeScale::Linear, Some(calibration)) => *val / full_scale * calibration,
+                (VolumeScale::Decibel { floor_db }, calibration) => {
+                    let floor_db = F::cast(*floor_db as f64).min(-F::epsilon());
+                    let db = (F::cast(20.0) * (*val / full_scale * calibration.unwrap_or_else(F::one)).log10()).max(floor_db);
+                    (db - floor_db) / -floor_db
+                }
+            };
             self.freq_buffer.push(Frequency {
-                volume: *val * self.config.volume,
+                volume: volume * F::cast(self.config.volume as f64),
                 position: percentage,
-                freq: percentage * (self.config.sampling_rate as f32 / 2.0),
+                freq: percentage * (F::cast(self.config.sampling_rate as f64) / F::cast(2.0)),
             });
         }
     }
 
     /// makes low frequencies in `freq_buffer` occupy more space so that result is more "readable"
     pub fn normalize_frequency_position(&mut self) {
-        match self.config.position_normalisation {
-            PositionNormalisation::Linear => (), // already done in `self.raw_to_freq_buffer()`
-            PositionNormalisation::Exponential => {
+        match &self.config.frequency_axis {
+            FrequencyAxis::Linear => (), // already done in `self.raw_to_freq_buffer()`
+            FrequencyAxis::Exponential => {
                 for freq in self.freq_buffer.iter_mut() {
                     freq.position = freq.position.sqrt();
                 } 
             }
-            PositionNormalisation::Harmonic => {
-                let mut pos: f32 = 0.0;
+            FrequencyAxis::Gamma(gamma) => {
+                for freq in self.freq_buffer.iter_mut() {
+                    freq.position = freq.position.powf(F::cast(gamma.max(MIN_GAMMA) as f64));
+                }
+            }
+            FrequencyAxis::Harmonic => {
+                let mut pos: F = F::zero();
                 for (i, freq) in self.freq_buffer.iter_mut().enumerate() {
                     freq.position = pos;
-                    pos += 1.0 / (i + 1) as f32;
+                    pos = pos + F::one() / F::cast((self.bin_offset + i + 1) as f64);
                 }
 
                 // last freq must have position of 1.0
                 let max_pos = match self.freq_buffer.last() {
                     Some(f) => f.position,
-                    None => 1.0
+                    None => F::one()
+                };
+                for freq in self.freq_buffer.iter_mut() {
+                    freq.position = freq.position * (F::one() / max_pos);
+                }
+            }
+            axis => {
+                let (first, last) = match (self.freq_buffer.first(), self.freq_buffer.last()) {
+                    (Some(first), Some(last)) => (axis.warp(first.freq.as_f64()), axis.warp(last.freq.as_f64())),
+                    _ => return,
                 };
+                let range = last - first;
                 for freq in self.freq_buffer.iter_mut() {
-                    freq.position *= 1.0 / max_pos;
+                    freq.position = if range > 0.0 { F::cast((axis.warp(freq.freq.as_f64()) - first) / range) } else { F::zero() };
                 }
             }
         }
     }
 
+    /// metadata of last `interpolate()` call, `None` if it was not called yet
+    ///
+    /// useful to debug configs, because with some modes output length differs from `config.resolution`
+    pub fn last_interpolation_info(&self) -> Option<&InterpolationInfo> {
+        self.last_interpolation.as_ref()
+    }
+
     /// applies the position of frequencies in `freq_buffer`
     /// 
-    /// interpolates the gaps and applies resolution
+    /// interpolates the gaps and applies resolution,
+    /// except for `Interpolation::None` every resulting bar gets the position it is drawn at
+    /// and the frequency that corresponds to this position, so `freq` always matches the place of a bar
+    ///
+    /// afterwards the bar nearest to every `pinned_frequencies` of config is moved onto it,
+    /// together with its position, so bars stay where their frequency lies
     pub fn interpolate(&mut self) {
+        let input_len: usize = self.freq_buffer.len();
+        self.interpolate_freq_buffer();
+        self.last_interpolation = Some(InterpolationInfo {
+            mode: self.config.interpolation.clone(),
+            input_len,
+            output_len: self.freq_buffer.len(),
+        });
+    }
+
+    // every interpolated bar gets the position it is drawn at and the frequency that belongs to this position
+    fn interpolate_freq_buffer(&mut self) {
         let resolution = match self.config.resolution {
             Some(res) => res,
             None => self.freq_buffer.len(),
         };
+        let source = self.freq_buffer.clone();
         self.freq_buffer = match self.config.interpolation {
             ConfigInterpolation::None => self.freq_buffer.clone(),
             ConfigInterpolation::Gaps => {
-                let mut o_buf: Vec<Frequency> = vec![Frequency::empty(); resolution];
+                let mut o_buf: Vec<Frequency<F>> = vec![Frequency::empty(); resolution];
                 for freq in self.freq_buffer.iter() {
-                    let abs_pos = (o_buf.len() as f32 * freq.position) as usize;
+                    let abs_pos = as_index(F::cast(o_buf.len() as f64) * freq.position);
                     if o_buf.len() > abs_pos {
                         // louder freqs are more important and shall not be overwritten by others
                         if freq.volume > o_buf[abs_pos].volume {
@@ -212,21 +559,21 @@ impl Processor {
                 o_buf
             }
             ConfigInterpolation::Step => {
-                let mut o_buf: Vec<Frequency> = vec![Frequency::empty(); resolution];
+                let mut o_buf: Vec<Frequency<F>> = vec![Frequency::empty(); resolution];
                 let mut freqs = self.freq_buffer.iter().peekable();
 
                 'filling: loop {
-                    let freq: &Frequency = match freqs.next() {
+                    let freq: &Frequency<F> = match freqs.next() {
                         Some(f) => f,
                         None => break 'filling,
                     };
-                    let freq2: &Frequency = match freqs.peek() {
+                    let freq2: &Frequency<F> = match freqs.peek() {
                         Some(f) => f,
                         None => break 'filling,
                     };
 
-                    let start: usize = (freq.position * o_buf.len() as f32) as usize;
-                    let end: usize = (freq2.position * o_buf.len() as f32) as usize;
+                    let start: usize = as_index(freq.position * F::cast(o_buf.len() as f64));
+                    let end: usize = as_index(freq2.position * F::cast(o_buf.len() as f64));
 
                     for i in start..=end {
                         if i < o_buf.len() {
@@ -238,39 +585,39 @@ impl Processor {
                 o_buf
             }
             ConfigInterpolation::Linear => {
-                let mut o_buf: Vec<Frequency> = vec![Frequency::empty(); resolution];
+                let mut o_buf: Vec<Frequency<F>> = vec![Frequency::empty(); resolution];
                 let mut freqs = self.freq_buffer.iter().peekable();
                 'linear: loop {
-                    let start_freq: &Frequency = match freqs.next() {
+                    let start_freq: &Frequency<F> = match freqs.next() {
                         Some(f) => f,
                         None => break 'linear,
                     };
 
-                    let start: usize = (start_freq.position * o_buf.len() as f32) as usize;
+                    let start: usize = as_index(start_freq.position * F::cast(o_buf.len() as f64));
                     let end_freq = match freqs.peek() {
                         Some(f) => f,
                         None => break 'linear,
                     };
-                    let end: usize = (end_freq.position * o_buf.len() as f32) as usize;
+                    let end: usize = as_index(end_freq.position * F::cast(o_buf.len() as f64));
 
                     if start < resolution && end < resolution {
                         for i in start..=end {
                             let pos: usize = i - start;
                             let gap_size = end - start;
-                            let mut percentage: f32 = pos as f32 / gap_size as f32;
-                            if percentage.is_nan() {percentage = 0.5}
+                            let mut percentage: F = F::cast(pos as f64) / F::cast(gap_size as f64);
+                            if percentage.is_nan() {percentage = F::cast(0.5)}
 
                             // interpolation
-                            let volume: f32 = (start_freq.volume * (1.0 - percentage))
+                            let volume: F = (start_freq.volume * (F::one() - percentage))
                                 + (end_freq.volume * percentage);
 
-                            let freq: f32 = (start_freq.freq * (1.0 - percentage))
+                            let freq: F = (start_freq.freq * (F::one() - percentage))
                                 + (end_freq.freq * percentage);
 
                             if o_buf.len() > i && o_buf[i].volume < volume {
                                 o_buf[i] = Frequency {
                                     volume,
-                                    position: 0.0, // unneccessary 
+                                    position: F::zero(), // unneccessary 
                                     freq,
                                 };
                             }
@@ -280,9 +627,10 @@ impl Processor {
                 o_buf
             }
             ConfigInterpolation::Cubic => {
-                let mut o_buf: Vec<Frequency> = vec![Frequency::empty(); resolution];
+                let mut o_buf: Vec<Frequency<F>> = vec![Frequency::empty(); resolution];
 
                 let mut fb = self.freq_buffer.clone();
+                let tension: F = F::cast(self.config.cubic_tension.clamp(0.0, 1.0) as f64);
 
                 fb.insert(0, Frequency::empty());
                 fb.push( Frequency::empty() );
@@ -294,15 +642,15 @@ impl Processor {
                         let y2 = fb[i+2].volume;
                         let y3 = fb[i+3].volume;
     
-                        let start = ( fb[i+1].position * o_buf.len() as f32 ) as usize;
-                        let end = ( fb[i+2].position * o_buf.len() as f32 ) as usize;
+                        let start = as_index( fb[i+1].position * F::cast(o_buf.len() as f64) );
+                        let end = as_index( fb[i+2].position * F::cast(o_buf.len() as f64) );
     
                         if start < resolution && end < resolution {
                             for j in start..=end {
                                 let pos: usize = j - start;
                                 let gap_size = end - start;
-                                let mut percentage: f32 = pos as f32 / gap_size as f32;
-                                if percentage.is_nan() {percentage = 0.5}
+                                let mut percentage: F = F::cast(pos as f64) / F::cast(gap_size as f64);
+                                if percentage.is_nan() {percentage = F::cast(0.5)}
     
                                 let t = percentage;
                                 let t2 = percentage.powi(2);
@@ -313,6 +661,11 @@ impl Processor {
                                 let a1  = y0 - y1 - a0;
                                 let a2 = y2 - y0;
                                 let a3 = y1;
+
+                                // tension blends cubic coefficients towards a straight line from y1 to y2
+                                let a0 = a0 * (F::one() - tension);
+                                let a1 = a1 * (F::one() - tension);
+                                let a2 = a2 * (F::one() - tension) + (y2 - y1) * tension;
     
                                 // math magic
                                 let volume = a0 * t * t2 + a1 * t2 + a2 * t + a3;
@@ -320,12 +673,12 @@ impl Processor {
                                 // linear freq interpolation
                                 let f1 = fb[i+1].freq;
                                 let f2 = fb[i+2].freq;
-                                let freq = f1 * (1.0 - t) + f2 * t;
+                                let freq = f1 * (F::one() - t) + f2 * t;
     
                                 if o_buf.len() > j && o_buf[j].volume < volume {
                                     o_buf[j] = Frequency {
                                         volume,
-                                        position: 0.0, // unneccessary 
+                                        position: F::zero(), // unneccessary 
                                         freq,
                                     };
                                 }
@@ -337,6 +690,39 @@ impl Processor {
                 o_buf
             }
         };
+
+        if self.config.interpolation != ConfigInterpolation::None {
+            let len = self.freq_buffer.len();
+            for (i, freq) in self.freq_buffer.iter_mut().enumerate() {
+                freq.position = F::cast(i as f64) / F::cast(len as f64);
+                freq.freq = freq_at_position(&source, freq.position);
+            }
+        }
+
+        // nearest bar of every pinned frequency gets moved onto it
+        let (low, high) = match (source.first(), source.last()) {
+            (Some(first), Some(last)) => (first.freq, last.freq),
+            _ => return,
+        };
+        let mut taken: Vec<usize> = Vec::new();
+        for pinned in self.config.pinned_frequencies.iter() {
+            let pinned = F::cast(*pinned as f64);
+            if pinned < low || pinned > high {
+                continue;
+            }
+            let nearest = self.freq_buffer
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !taken.contains(i))
+                .min_by(|a, b| (a.1.freq - pinned).abs().partial_cmp(&(b.1.freq - pinned).abs()).unwrap_or(std::cmp::Ordering::Equal))
+                .map(|(i, _)| i);
+            if let Some(i) = nearest {
+                self.freq_buffer[i].freq = pinned;
+                self.freq_buffer[i].volume = volume_at(&source, pinned);
+                self.freq_buffer[i].position = position_at(&source, pinned);
+                taken.push(i);
+            }
+        }
     }
 
     /// applies frequency boundaries
@@ -349,7 +735,7 @@ impl Processor {
             if i >= self.freq_buffer.len() {
                 break;
             }
-            if self.freq_buffer[i].freq > self.config.frequency_bounds[0] as f32 {
+            if self.freq_buffer[i].freq > F::cast(self.config.frequency_bounds[0] as f64) {
                 start = i;
                 break;
             }
@@ -364,7 +750,7 @@ impl Processor {
                 break;
             }
             if self.freq_buffer[self.freq_buffer.len() - (i + 1)].freq
-                < self.config.frequency_bounds[1] as f32
+                < F::cast(self.config.frequency_bounds[1] as f64)
             {
                 end = self.freq_buffer.len() - i;
                 break;
@@ -376,16 +762,246 @@ impl Processor {
         let mut bound_buff = self.freq_buffer[start..end].to_vec();
         if !bound_buff.is_empty() {
             // fix for first and last frequency's position not being 0 and 1
-            let start_pos: f32 = bound_buff[0].position;
-            let end_pos: f32 = bound_buff[bound_buff.len() - 1].position - start_pos;
-            let end_pos_offset: f32 = 1.0 / end_pos;
+            let start_pos: F = bound_buff[0].position;
+            let end_pos: F = bound_buff[bound_buff.len() - 1].position - start_pos;
+            let end_pos_offset: F = F::one() / end_pos;
 
             for freq in bound_buff.iter_mut() {
-                freq.position -= start_pos;
-                freq.position *= end_pos_offset;
+                freq.position = freq.position - start_pos;
+                freq.position = freq.position * end_pos_offset;
             }
 
             self.freq_buffer = bound_buff;
         }
     }
+
+    /// every frequency of `freq_buffer` together with the index of the FFT bin in `raw_buffer` it originates from
+    ///
+    /// index is derived from `freq`, so after interpolation or distribution it is only the nearest bin.
+    /// requires `raw_to_freq_buffer()` to have been called, otherwise `freq_buffer` is assumed to contain one frequency per bin
+    pub fn indexed_frequencies(&self) -> Vec<(usize, Frequency<F>)> {
+        let bins: usize = if self.bin_count > 0 { self.bin_count } else { self.freq_buffer.len() };
+        let nyquist: F = F::cast(self.config.sampling_rate as f64) / F::cast(2.0);
+        self.freq_buffer
+            .iter()
+            .map(|f| {
+                // inverse of `raw_to_freq_buffer()`
+                let index = as_index((f.freq / nyquist * F::cast(bins as f64)).round());
+                (index.saturating_sub(1).min(bins.saturating_sub(1)), f.clone())
+            })
+            .collect()
+    }
+
+    /// volume at `hz`, linearly interpolated between the two surrounding frequencies in `freq_buffer`
+    ///
+    /// accuracy depends on how dense frequencies are around `hz`,
+    /// returns 0.0 if `hz` is outside of the range covered by `freq_buffer`
+    pub fn volume_at(&self, hz: F) -> F {
+        volume_at(&self.freq_buffer, hz)
+    }
+
+    /// approximate ± hz uncertainty of `freq` of bar `bar_index` in `freq_buffer`, `NaN` if out of range
+    ///
+    /// half of the larger one of FFT bin width and local spacing between neighbouring bars,
+    /// so bars that got interpolated between bins at low frequencies are limited by bin width
+    /// and bars that merge multiple bins at high frequencies by their spacing.
+    /// it is an estimate of resolution, not of the actual measurement error
+    pub fn frequency_uncertainty(&self, bar_index: usize) -> F {
+        let freq = match self.freq_buffer.get(bar_index) {
+            Some(f) => f.freq,
+            None => return F::nan(),
+        };
+        let two = F::cast(2.0);
+        let bin_width: F = if self.bin_count > 0 {
+            F::cast(self.config.sampling_rate as f64) / two / F::cast(self.bin_count as f64)
+        } else {
+            F::zero()
+        };
+
+        // mean distance to direct neighbours, one-sided at edges
+        let neighbours: Vec<F> = [bar_index.checked_sub(1), Some(bar_index + 1)]
+            .iter()
+            .filter_map(|i| i.and_then(|i| self.freq_buffer.get(i)))
+            .map(|f| (f.freq - freq).abs())
+            .collect();
+        let spacing: F = if neighbours.is_empty() { F::zero() } else { neighbours.iter().copied().sum::<F>() / F::cast(neighbours.len() as f64) };
+
+        bin_width.max(spacing) / two
+    }
+
+    /// subtracts estimated noise floor from every volume of `freq_buffer`, volumes never fall below 0.0
+    ///
+    /// floor is the `percentile` (0..=1) of all volumes, with 0.2 meaning that 20% of all frequencies
+    /// are at or below it, so these read as zero afterwards.
+    /// returns the estimated floor
+    pub fn subtract_noise_floor(&mut self, percentile: F) -> F {
+        if self.freq_buffer.is_empty() {
+            return F::zero();
+        }
+        let mut volumes: Vec<F> = self.freq_buffer.iter().map(|f| f.volume).collect();
+        volumes.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let percentile = percentile.max(F::zero()).min(F::one());
+        let index = as_index((percentile * F::cast((volumes.len() - 1) as f64)).round());
+        let floor = volumes[index];
+
+        for freq in self.freq_buffer.iter_mut() {
+            freq.volume = (freq.volume - floor).max(F::zero());
+        }
+
+        floor
+    }
+
+    /// spectral flatness of `freq_buffer`, geometric mean of volumes divided by their arithmetic mean
+    ///
+    /// close to 1.0 for noise and close to 0.0 for pure tones,
+    /// a tiny epsilon gets added to every volume so that silent frequencies do not result in `ln(0)`
+    ///
+    /// returns 0.0 if `freq_buffer` is empty
+    pub fn spectral_flatness(&self) -> F {
+        let epsilon: F = F::cast(1e-10);
+        if self.freq_buffer.is_empty() {
+            return F::zero();
+        }
+        let len = F::cast(self.freq_buffer.len() as f64);
+
+        let log_mean: F = self.freq_buffer.iter().map(|f| (f.volume + epsilon).ln()).sum::<F>() / len;
+        let mean: F = self.freq_buffer.iter().map(|f| f.volume + epsilon).sum::<F>() / len;
+
+        log_mean.exp() / mean
+    }
+
+    /// folds `freq_buffer` into 12 pitch classes, starting at C
+    ///
+    /// energy (volume squared) of every frequency gets summed into its nearest semitone,
+    /// result is normalized so that the loudest pitch class is 1.0
+    ///
+    /// tuning of A4 = 440hz is assumed, frequencies below 20hz are ignored
+    pub fn chroma(&self) -> [F; 12] {
+        let mut chroma: [F; 12] = [F::zero(); 12];
+        for freq in self.freq_buffer.iter() {
+            if freq.freq < F::cast(20.0) {
+                continue;
+            }
+            let note = freq_to_note(freq.freq.as_f64() as f32).round() as i32;
+            let class = note.rem_euclid(12) as usize;
+            chroma[class] = chroma[class] + freq.volume.powi(2);
+        }
+
+        let max = chroma.iter().cloned().fold(F::zero(), F::max);
+        if max > F::zero() {
+            for c in chroma.iter_mut() {
+                *c = *c / max;
+            }
+        }
+
+        chroma
+    }
+
+    /// `n` most prominent peaks of `freq_buffer`, sorted by prominence
+    ///
+    /// a peak is a local maximum, louder than its right and at least as loud as its left neighbour.
+    /// its prominence is its volume minus the higher of the two lowest points
+    /// between it and the next louder frequency (or the end of buffer) on either side.
+    /// peaks closer than `min_separation_hz` to an already chosen, more prominent peak get skipped
+    pub fn prominent_peaks(&self, n: usize, min_separation_hz: F) -> Vec<Frequency<F>> {
+        let buffer = &self.freq_buffer;
+        let len = buffer.len();
+
+        let mut peaks: Vec<(usize, F)> = Vec::new();
+        for i in 0..len {
+            let volume = buffer[i].volume;
+            let left = i.checked_sub(1).map(|l| buffer[l].volume).unwrap_or_else(F::min_value);
+            let right = buffer.get(i + 1).map(|r| r.volume).unwrap_or_else(F::min_value);
+            if volume < left || volume <= right {
+                continue;
+            }
+
+            let mut left_min = volume;
+            for f in buffer[..i].iter().rev() {
+                if f.volume > volume {
+                    break;
+                }
+                left_min = left_min.min(f.volume);
+            }
+            let mut right_min = volume;
+            for f in buffer[i + 1..].iter() {
+                if f.volume > volume {
+                    break;
+                }
+                right_min = right_min.min(f.volume);
+            }
+
+            peaks.push((i, volume - left_min.max(right_min)));
+        }
+        peaks.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut chosen: Vec<Frequency<F>> = Vec::with_capacity(n);
+        for (i, _) in peaks {
+            if chosen.len() >= n {
+                break;
+            }
+            let freq = &buffer[i];
+            if chosen.iter().all(|c| (c.freq - freq.freq).abs() >= min_separation_hz) {
+                chosen.push(freq.clone());
+            }
+        }
+
+        chosen
+    }
+}
+
+// same as `value as usize` for `f32`, negative values and `NaN` saturate to 0
+fn as_index<F: Float>(value: F) -> usize {
+    value.as_f64() as usize
+}
+
+// frequency at `position`, linearly interpolated between the two surrounding frequencies of `buffer`
+fn freq_at_position<F: Float>(buffer: &[Frequency<F>], position: F) -> F {
+    let upper = buffer.partition_point(|f| f.position < position);
+    match (upper.checked_sub(1).and_then(|i| buffer.get(i)), buffer.get(upper)) {
+        (Some(low), Some(high)) => {
+            let diff = high.position - low.position;
+            if diff <= F::zero() {
+                return low.freq;
+            }
+            let t = (position - low.position) / diff;
+            low.freq + (high.freq - low.freq) * t
+        }
+        (None, Some(f)) | (Some(f), None) => f.freq,
+        (None, None) => F::zero(),
+    }
+}
+
+// position of `hz`, linearly interpolated between the two surrounding frequencies of `buffer`
+fn position_at<F: Float>(buffer: &[Frequency<F>], hz: F) -> F {
+    let upper = buffer.partition_point(|f| f.freq < hz);
+    match (upper.checked_sub(1).and_then(|i| buffer.get(i)), buffer.get(upper)) {
+        (_, Some(high)) if high.freq == hz => high.position,
+        (Some(low), Some(high)) => {
+            let diff = high.freq - low.freq;
+            if diff <= F::zero() {
+                return low.position;
+            }
+            let t = (hz - low.freq) / diff;
+            low.position + (high.position - low.position) * t
+        }
+        _ => F::zero(),
+    }
+}
+
+// volume at `hz`, linearly interpolated between the two surrounding frequencies of `buffer`
+fn volume_at<F: Float>(buffer: &[Frequency<F>], hz: F) -> F {
+    let upper = buffer.partition_point(|f| f.freq < hz);
+    match (upper.checked_sub(1).and_then(|i| buffer.get(i)), buffer.get(upper)) {
+        (_, Some(high)) if high.freq == hz => high.volume,
+        (Some(low), Some(high)) => {
+            let diff = high.freq - low.freq;
+            if diff <= F::zero() {
+                return low.volume;
+            }
+            let t = (hz - low.freq) / diff;
+            low.volume + (high.volume - low.volume) * t
+        }
+        _ => F::zero(),
+    }
 }
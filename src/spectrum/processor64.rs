@@ -0,0 +1,23 @@
+//! ## Example
+//! ```
+//! use audioviz::spectrum::config::ProcessorConfig;
+//! use audioviz::spectrum::processor64::{Processor64, Frequency64};
+//!
+//! let data: Vec<f64> = vec![0.0, 1.0, 0.0, 0.5, -1.0, 0.043];
+//!
+//! let mut processor = Processor64::from_raw_data(ProcessorConfig::default(), data);
+//! processor.compute_all();
+//!
+//! let frequencies: Vec<Frequency64> = processor.freq_buffer;
+//! ```
+
+use crate::spectrum::{processor::Processor, Frequency};
+
+/// same as `Frequency` but with f64 precision, converts into `Frequency` with `From`
+pub type Frequency64 = Frequency<f64>;
+
+/// `processor::Processor` that processes everything in f64
+///
+/// slower, but precision is kept over the long normalisation and interpolation chain,
+/// which can matter for offline analysis with a high dynamic range
+pub type Processor64 = Processor<f64>;
@@ -0,0 +1,93 @@
+//! ## Example
+//! ```
+//! use audioviz::spectrum::{Frequency, render::{spectrogram_to_image, Colormap}};
+//!
+//! // frames of processed frequencies, for example collected from `Stream::get_frequencies()`
+//! let frames: Vec<Vec<Frequency>> = vec![vec![Frequency::empty(); 64]; 100];
+//!
+//! let image = spectrogram_to_image(&frames, 200, 64, Colormap::Viridis);
+//! // image.save("spectrogram.png") with the `png` feature of `image`
+//! assert_eq!(image.dimensions(), (200, 64));
+//! ```
+
+use image::{Rgb, RgbImage};
+
+use super::Frequency;
+
+/// mapping of volume to color
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Colormap {
+    Viridis,
+    Magma,
+    Grayscale,
+}
+impl Colormap {
+    /// color of `value`, which gets clamped to (0..=1)
+    pub fn color(&self, value: f32) -> [u8; 3] {
+        let value = if value.is_nan() { 0.0 } else { value.clamp(0.0, 1.0) };
+        let stops: &[[u8; 3]] = match self {
+            Colormap::Grayscale => return [(value * 255.0).round() as u8; 3],
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Magma => &MAGMA,
+        };
+
+        // linear interpolation between evenly spaced stops
+        let scaled = value * (stops.len() - 1) as f32;
+        let i = (scaled as usize).min(stops.len() - 2);
+        let t = scaled - i as f32;
+
+        let mut color = [0_u8; 3];
+        for (c, (a, b)) in color.iter_mut().zip(stops[i].iter().zip(stops[i + 1].iter())) {
+            *c = (*a as f32 + (*b as f32 - *a as f32) * t).round() as u8;
+        }
+        color
+    }
+}
+
+// sampled from matplotlib colormaps
+const VIRIDIS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 44, 122],
+    [59, 81, 139],
+    [44, 113, 142],
+    [33, 144, 141],
+    [39, 173, 129],
+    [92, 200, 99],
+    [170, 220, 50],
+    [253, 231, 37],
+];
+const MAGMA: [[u8; 3]; 9] = [
+    [0, 0, 4],
+    [28, 16, 68],
+    [79, 18, 123],
+    [129, 37, 129],
+    [181, 54, 122],
+    [229, 80, 100],
+    [251, 135, 97],
+    [254, 194, 135],
+    [252, 253, 191],
+];
+
+/// renders frames as heatmap, time goes from left to right and frequency from bottom to top
+///
+/// frames and their frequencies get stretched or squeezed to fill `width` * `height` pixels,
+/// every pixel takes the volume of its nearest frequency
+pub fn spectrogram_to_image(frames: &[Vec<Frequency>], width: u32, height: u32, colormap: Colormap) -> RgbImage {
+    let mut image = RgbImage::new(width, height);
+    if frames.is_empty() {
+        return image;
+    }
+
+    for x in 0..width {
+        let frame = &frames[x as usize * frames.len() / width as usize];
+        if frame.is_empty() {
+            continue;
+        }
+        for y in 0..height {
+            let bar = (height - 1 - y) as usize * frame.len() / height as usize;
+            image.put_pixel(x, y, Rgb(colormap.color(frame[bar].volume)));
+        }
+    }
+
+    image
+}
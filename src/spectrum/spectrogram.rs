@@ -0,0 +1,144 @@
+//! ## Example
+//! ```
+//! use audioviz::spectrum::{Frequency, Spectrogram};
+//!
+//! let frames: Vec<Vec<Frequency>> = vec![vec![Frequency::empty(); 4]; 2];
+//! let spectrogram = Spectrogram::from_frames(&frames, 44_100);
+//!
+//! // any `std::io::Write` like `std::fs::File`, `save()` and `load()` work on paths directly
+//! let mut bytes: Vec<u8> = Vec::new();
+//! spectrogram.write_to(&mut bytes).unwrap();
+//!
+//! let loaded = Spectrogram::read_from(&bytes[..]).unwrap();
+//! assert_eq!(loaded, spectrogram);
+//! ```
+//!
+//! ## Byte layout
+//! all numbers are little endian
+//!
+//! | bytes              | content                                  |
+//! |--------------------|------------------------------------------|
+//! | 4                  | magic `AVSG`                             |
+//! | 4                  | format version as u32, currently 1       |
+//! | 4                  | sampling rate as u32                     |
+//! | 4                  | frame count as u32                       |
+//! | 4                  | bin count as u32                         |
+//! | 4 * bins           | frequency in hz of every bin as f32      |
+//! | 4 * frames * bins  | volumes as f32, frame after frame        |
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use super::Frequency;
+
+const MAGIC: &[u8; 4] = b"AVSG";
+const VERSION: u32 = 1;
+
+/// volumes of successive frames with shared frequencies, for caching of analysis results
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spectrogram {
+    pub sampling_rate: u32,
+
+    /// frequency in hz of every bin
+    pub frequencies: Vec<f32>,
+
+    /// volumes of every bin, one `Vec` per frame with the same length as `frequencies`
+    pub frames: Vec<Vec<f32>>,
+}
+impl Spectrogram {
+    /// takes frequencies of the first frame, assumes same number of frequencies in every frame
+    ///
+    /// shorter frames get padded with 0.0, longer ones truncated
+    pub fn from_frames(frames: &[Vec<Frequency>], sampling_rate: u32) -> Self {
+        let frequencies: Vec<f32> = match frames.first() {
+            Some(frame) => frame.iter().map(|f| f.freq).collect(),
+            None => Vec::new(),
+        };
+        let frames = frames
+            .iter()
+            .map(|frame| {
+                let mut volumes: Vec<f32> = frame.iter().map(|f| f.volume).collect();
+                volumes.resize(frequencies.len(), 0.0);
+                volumes
+            })
+            .collect();
+
+        Spectrogram { sampling_rate, frequencies, frames }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let bin_count = self.frequencies.len();
+        if self.frames.iter().any(|frame| frame.len() != bin_count) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "frames differ in length from frequencies"));
+        }
+
+        writer.write_all(MAGIC)?;
+        for value in [VERSION, self.sampling_rate, to_u32(self.frames.len())?, to_u32(bin_count)?] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        for value in self.frequencies.iter().chain(self.frames.iter().flatten()) {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// fails with `io::ErrorKind::InvalidData` if header is invalid
+    /// and with `io::ErrorKind::UnexpectedEof` if data is missing
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a spectrogram"));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid_data("unsupported spectrogram version"));
+        }
+        let sampling_rate = read_u32(&mut reader)?;
+        let frame_count = read_u32(&mut reader)? as usize;
+        let bin_count = read_u32(&mut reader)? as usize;
+
+        let frequencies = read_f32s(&mut reader, bin_count)?;
+        let mut frames: Vec<Vec<f32>> = Vec::new();
+        for _ in 0..frame_count {
+            frames.push(read_f32s(&mut reader, bin_count)?);
+        }
+
+        Ok(Spectrogram { sampling_rate, frequencies, frames })
+    }
+}
+
+fn to_u32(len: usize) -> io::Result<u32> {
+    u32::try_from(len).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "spectrogram too large"))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+// capacity is capped, so that a corrupt header can not cause a huge allocation up front
+fn read_f32s<R: Read>(reader: &mut R, count: usize) -> io::Result<Vec<f32>> {
+    let mut values: Vec<f32> = Vec::with_capacity(count.min(4096));
+    let mut bytes = [0u8; 4];
+    for _ in 0..count {
+        reader.read_exact(&mut bytes)?;
+        values.push(f32::from_le_bytes(bytes));
+    }
+    Ok(values)
+}
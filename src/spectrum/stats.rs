@@ -0,0 +1,104 @@
+//! ## Example of auto-ranging
+//! ```
+//! use audioviz::spectrum::{Frequency, stats::VolumeStats};
+//!
+//! let mut stats = VolumeStats::default();
+//!
+//! let frequencies: Vec<Frequency> = vec![Frequency::empty(); 10];
+//! stats.push(&frequencies);
+//!
+//! // top of display, rare spikes are ignored
+//! let top = stats.percentile(0.95);
+//! ```
+
+use std::collections::VecDeque;
+
+use super::Frequency;
+
+/// approximate statistics over volumes of recent frames
+///
+/// volumes are sorted into `buckets` equally sized buckets in range (0..=`max_volume`),
+/// louder volumes are counted in the last bucket.
+/// so resolution of reported values is `max_volume / buckets`
+///
+/// only the last `window` pushed frames are considered
+#[derive(Clone, Debug)]
+pub struct VolumeStats {
+    window: usize,
+    max_volume: f32,
+
+    // histogram of every frame in window
+    frames: VecDeque<Vec<u32>>,
+    // sum of all histograms in `frames`
+    histogram: Vec<u64>,
+}
+impl VolumeStats {
+    pub fn new(window: usize, buckets: usize, max_volume: f32) -> Self {
+        let buckets = buckets.max(1);
+        Self {
+            window: window.max(1),
+            max_volume,
+            frames: VecDeque::with_capacity(window),
+            histogram: vec![0; buckets],
+        }
+    }
+
+    /// adds volumes of single frame and removes oldest frame if window is full
+    pub fn push(&mut self, freqs: &[Frequency]) {
+        let buckets = self.histogram.len();
+        let mut frame: Vec<u32> = vec![0; buckets];
+        for freq in freqs.iter() {
+            let bucket = (freq.volume / self.max_volume * buckets as f32) as usize;
+            frame[bucket.min(buckets - 1)] += 1;
+        }
+
+        for (total, count) in self.histogram.iter_mut().zip(frame.iter()) {
+            *total += *count as u64;
+        }
+        self.frames.push_back(frame);
+
+        if self.frames.len() > self.window {
+            if let Some(old) = self.frames.pop_front() {
+                for (total, count) in self.histogram.iter_mut().zip(old.iter()) {
+                    *total -= *count as u64;
+                }
+            }
+        }
+    }
+
+    /// volume below which `p` (0..=1) of all volumes in window are
+    ///
+    /// linearly interpolated inside of bucket, returns 0.0 if nothing got pushed
+    pub fn percentile(&self, p: f32) -> f32 {
+        let total: u64 = self.histogram.iter().sum();
+        if total == 0 {
+            return 0.0;
+        }
+        let bucket_size: f32 = self.max_volume / self.histogram.len() as f32;
+        let target: f32 = p.clamp(0.0, 1.0) * total as f32;
+
+        let mut cumulative: u64 = 0;
+        for (i, count) in self.histogram.iter().enumerate() {
+            if *count > 0 && (cumulative + count) as f32 >= target {
+                let inside: f32 = (target - cumulative as f32) / *count as f32;
+                return (i as f32 + inside) * bucket_size;
+            }
+            cumulative += count;
+        }
+
+        self.max_volume
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        for total in self.histogram.iter_mut() {
+            *total = 0;
+        }
+    }
+}
+impl Default for VolumeStats {
+    /// window of 60 frames and 256 buckets in range (0..=1)
+    fn default() -> Self {
+        Self::new(60, 256, 1.0)
+    }
+}
@@ -20,16 +20,62 @@
 //!     └─────────────────────────┘
 //! ```
 
-use super::config::StreamConfig;
-use super::{processor::Processor, Frequency};
+use super::config::{StreamConfig, ProcessorConfig, UnderrunBehavior};
+use super::{processor::Processor, Frequency, frequencies_as_u8};
 use crate::utils::seperate_channels;
+use crate::fft::Complex;
+#[cfg(feature = "parallel")]
+use super::pool::Pool;
+
+use std::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+type FrameCallback = Box<dyn FnMut(&[Vec<Frequency>]) + Send>;
+pub(crate) type Spectrum = (Vec<Frequency>, Option<Vec<Complex<f32>>>);
 
 /// abstraction over `processor::Processor` with additional effects like gravity
 pub struct Stream {
     pub config: StreamConfig,
     raw_buffer: Vec<Vec<f32>>,
     freq_buffer: Vec<Vec<Frequency>>,
+    // bounded and interpolated `freq_buffer`, returned by `get_frequencies()`
+    frequencies: Vec<Vec<Frequency>>,
+    previous_freq_buffer: Vec<Vec<Frequency>>,
     gravity_time_buffer: Vec<Vec<u32>>,
+    agc_max_buffer: Vec<Vec<f32>>,
+    complex_buffer: Vec<Vec<Complex<f32>>>,
+    on_frame: Option<FrameCallback>,
+    locked_positions: Option<(ProcessorConfig, Vec<Vec<f32>>)>,
+    // frame of `push_magnitudes()` that gets processed on next `update()` instead of `raw_buffer`
+    magnitudes: Option<Vec<Vec<f32>>>,
+
+    // samples per channel pushed since creation or `reset_clock()`, and its value at the last computed frame
+    sample_clock: u64,
+    frame_clock: u64,
+
+    // true if last `update()` did not have enough audio for a FFT
+    starved: bool,
+
+    // true after `check_sample_rate()` warned once
+    sample_rate_warned: bool,
+
+    // worker threads of `compute_spectra()`, created on first use
+    #[cfg(feature = "parallel")]
+    pool: Option<Pool>,
+
+    #[cfg(feature = "std")]
+    last_fft: Option<Instant>,
+
+    // frequencies and time of the frame before the current one, and time of the current one
+    #[cfg(feature = "std")]
+    previous_frame: Option<(Vec<Vec<Frequency>>, Instant, Instant)>,
+
+    // processor config of last output, and output before the config changed with time of change
+    #[cfg(feature = "std")]
+    transition_config: Option<ProcessorConfig>,
+    #[cfg(feature = "std")]
+    transition: Option<(Vec<Vec<Frequency>>, Instant)>,
 }
 impl Stream {
     pub fn new(config: StreamConfig) -> Self {
@@ -38,9 +84,43 @@ impl Stream {
             config,
             raw_buffer: Vec::with_capacity(cap),
             freq_buffer: Vec::with_capacity(cap),
+            frequencies: Vec::new(),
+            previous_freq_buffer: Vec::with_capacity(cap),
             gravity_time_buffer: Vec::with_capacity(cap),
+            agc_max_buffer: Vec::with_capacity(cap),
+            complex_buffer: Vec::new(),
+            on_frame: None,
+            locked_positions: None,
+            magnitudes: None,
+
+            sample_clock: 0,
+            frame_clock: 0,
+
+            starved: false,
+            sample_rate_warned: false,
+
+            #[cfg(feature = "parallel")]
+            pool: None,
+
+            #[cfg(feature = "std")]
+            last_fft: None,
+
+            #[cfg(feature = "std")]
+            previous_frame: None,
+
+            #[cfg(feature = "std")]
+            transition_config: None,
+            #[cfg(feature = "std")]
+            transition: None,
         }
     }
+    /// registers callback that gets called once for every newly computed frame
+    ///
+    /// it is executed synchronously inside of `update()`, but only if FFT actually ran,
+    /// and receives the same frequencies that `get_frequencies()` would return
+    pub fn set_on_frame(&mut self, cb: impl FnMut(&[Vec<Frequency>]) + Send + 'static) {
+        self.on_frame = Some(Box::new(cb));
+    }
     pub fn push_data(&mut self, data: Vec<f32>) {
         //self.raw_buffer.append(&mut data);
         let channels: usize = self.config.channel_count as usize;
@@ -51,69 +131,508 @@ impl Stream {
             let data = &mut data.clone();
             self.raw_buffer[channel].append(data);
         }
+        self.sample_clock += (data.len() / channels.max(1)) as u64;
+        self.trim_latency();
     }
+
+    /// same as `push_data()`, but with data that is already separated into one buffer per channel
+    ///
+    /// skips interleaving and deinterleaving, data gets ignored with a warning
+    /// if number of buffers does not equal `config.channel_count` or buffers differ in length
+    pub fn push_channels(&mut self, channels: &[Vec<f32>]) {
+        let channel_count: usize = self.config.channel_count as usize;
+        if channels.len() != channel_count {
+            log::warn!("pushed {} channels into stream with {} channels", channels.len(), channel_count);
+            return;
+        }
+        if channels.windows(2).any(|w| w[0].len() != w[1].len()) {
+            log::warn!("pushed channels of unequal length into stream");
+            return;
+        }
+        if self.raw_buffer.len() != channel_count {
+            self.raw_buffer = vec![vec![]; channel_count];
+        }
+        for (raw_data, data) in self.raw_buffer.iter_mut().zip(channels.iter()) {
+            raw_data.extend_from_slice(data);
+        }
+        self.sample_clock += channels.first().map(|c| c.len()).unwrap_or(0) as u64;
+        self.trim_latency();
+    }
+
+    /// pushes a frame of already computed magnitude spectra, one per channel, that skips `apodize()` and `fft()`
+    ///
+    /// next `update()` processes it instead of pushed audio, everything after the FFT like
+    /// normalisation, positioning, interpolation and gravity gets applied as usual.
+    /// every spectrum is expected in the layout of `Processor::raw_bins()`:
+    /// `fft_size / 2 + 1` magnitudes of bins from DC up to nyquist, bin `i` at `i * sampling_rate / fft_size` hz.
+    /// window and length of the upstream FFT are unknown, so `VolumeScale::Decibel` and `calibration`
+    /// treat a magnitude of 1.0 as full scale.
+    ///
+    /// frame gets ignored with a warning if number of spectra does not equal `config.channel_count`,
+    /// if it is called multiple times between updates only the last frame is processed.
+    /// an `update()` that skips the FFT because of `max_fft_rate` keeps the frame for the next one.
+    ///
+    /// every frame advances the clock of `get_frequencies_timed()` by `(len - 1) * 2` samples,
+    /// the FFT size of a spectrum with `len` magnitudes
+    pub fn push_magnitudes(&mut self, mags: Vec<Vec<f32>>) {
+        let channel_count: usize = self.config.channel_count as usize;
+        if mags.len() != channel_count {
+            log::warn!("pushed {} magnitude spectra into stream with {} channels", mags.len(), channel_count);
+            return;
+        }
+        let len = mags.iter().map(|m| m.len()).max().unwrap_or(0);
+        self.sample_clock += (len.saturating_sub(1) * 2) as u64;
+        self.magnitudes = Some(mags);
+    }
+
+    /// compares `data_rate`, the sampling rate of pushed audio like `Capture.sampling_rate`,
+    /// to `sampling_rate` of every processor config, returns false on mismatch
+    ///
+    /// warns only on the first mismatch of this stream, so it can be called before every `push_data()`.
+    /// a mismatch can be fixed with `StreamConfig::sync_sample_rate()`
+    pub fn check_sample_rate(&mut self, data_rate: u32) -> bool {
+        let channel_count: usize = (self.config.channel_count as usize).max(1);
+        let mismatch = (0..channel_count)
+            .map(|channel| self.config.processor_for(channel).sampling_rate)
+            .chain(std::iter::once(self.config.processor.sampling_rate))
+            .find(|rate| *rate != data_rate);
+
+        if let Some(rate) = mismatch {
+            if !self.sample_rate_warned {
+                log::warn!(
+                    "pushed audio has sampling rate of {} hz, but stream is configured for {} hz, see `StreamConfig::sync_sample_rate()`",
+                    data_rate,
+                    rate
+                );
+                self.sample_rate_warned = true;
+            }
+            return false;
+        }
+        true
+    }
+
+    // drops oldest samples that exceed `max_latency`
+    fn trim_latency(&mut self) {
+        if let Some(latency) = self.config.max_latency {
+            let max_len = (latency.as_secs_f64() * self.config.processor.sampling_rate as f64) as usize;
+            let max_len = max_len.max(self.config.fft_resolution + 1);
+            for raw_data in self.raw_buffer.iter_mut() {
+                if raw_data.len() > max_len {
+                    let diff = raw_data.len() - max_len;
+                    raw_data.drain(..diff);
+                }
+            }
+        }
+    }
+
+    /// true if last `update()` could not compute a new frame, because not enough audio was pushed
+    ///
+    /// what `get_frequencies()` returns then depends on `config.underrun_behavior`
+    pub fn is_starved(&self) -> bool {
+        self.starved
+    }
+
+    /// number of samples per channel that are buffered and wait for `update()`
+    pub fn buffered_len(&self) -> usize {
+        self.raw_buffer.iter().map(|b| b.len()).max().unwrap_or(0)
+    }
+
+    /// restarts the clock of `get_frequencies_timed()` at zero and forgets all stored `Instant`s
+    ///
+    /// should be called when switching to a different source, config and buffered audio are kept
+    pub fn reset_clock(&mut self) {
+        self.sample_clock = 0;
+        self.frame_clock = 0;
+
+        #[cfg(feature = "std")]
+        {
+            self.last_fft = None;
+            self.previous_frame = None;
+        }
+    }
+    /// frequencies of last `update()` call, bounded and interpolated
+    ///
+    /// result is computed once per `update()` and this only returns a copy of it,
+    /// so calling it multiple times between updates is cheap and does not affect gravity.
+    /// changes to `config` take effect on next `update()`
     pub fn get_frequencies(&mut self) -> Vec<Vec<Frequency>> {
-        let data = self.freq_buffer.clone();
+        self.frequencies.clone()
+    }
 
-        let channels: usize = self.config.channel_count as usize;
-        let mut buffer: Vec<Vec<Frequency>> = Vec::with_capacity(channels);
-        // additional effects get applied here, that were skiped on `self.update()`
-        for channel_data in data.iter() {
+    /// same as `get_frequencies()` but with the stream time of the newest sample the frame was computed from
+    ///
+    /// time is derived from samples pushed since creation or `reset_clock()` and `processor.sampling_rate`
+    pub fn get_frequencies_timed(&mut self) -> (Duration, Vec<Vec<Frequency>>) {
+        let rate = self.config.processor.sampling_rate.max(1) as f64;
+        let time = Duration::from_secs_f64(self.frame_clock as f64 / rate);
+        (time, self.get_frequencies())
+    }
+
+    // computes result of `get_frequencies()`
+    fn refresh_frequencies(&mut self) {
+        let mut buffer = self.finalize(&self.freq_buffer);
+
+        if self.config.lock_positions {
+            self.lock_positions(&mut buffer);
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(duration) = self.config.config_transition {
+            self.crossfade(&mut buffer, duration);
+        }
+
+        #[cfg(feature = "std")]
+        {
+            let now = Instant::now();
+            let last_time = self.previous_frame.as_ref().map(|(_, _, time)| *time).unwrap_or(now);
+            self.previous_frame = Some((std::mem::take(&mut self.frequencies), last_time, now));
+        }
+
+        self.frequencies = buffer;
+    }
+
+    // blends output of previous config into `buffer` until `duration` passed since config changed
+    #[cfg(feature = "std")]
+    fn crossfade(&mut self, buffer: &mut [Vec<Frequency>], duration: Duration) {
+        let changed = match &self.transition_config {
+            Some(config) => *config != self.config.processor,
+            None => false,
+        };
+        if changed && !self.frequencies.is_empty() {
+            self.transition = Some((self.frequencies.clone(), Instant::now()));
+        }
+        self.transition_config = Some(self.config.processor.clone());
+
+        let (old, start) = match &self.transition {
+            Some(t) => t,
+            None => return,
+        };
+        let t: f32 = if duration.is_zero() { 1.0 } else { start.elapsed().as_secs_f32() / duration.as_secs_f32() };
+        if t >= 1.0 {
+            self.transition = None;
+            return;
+        }
+
+        for (new, old) in buffer.iter_mut().zip(old.iter()) {
+            let old = resample(old, new);
+            for (freq, old) in new.iter_mut().zip(old.iter()) {
+                freq.volume = old.volume * (1.0 - t) + freq.volume * t;
+            }
+        }
+    }
+
+    /// blends the last two frames depending on how much time passed since the last one, for smooth output at any framerate
+    ///
+    /// at the time of the last frame the previous one is returned, one frame interval later the last one,
+    /// so this adds up to one frame of latency
+    #[cfg(feature = "std")]
+    pub fn get_frequencies_interpolated(&self, now: Instant) -> Vec<Vec<Frequency>> {
+        let (previous, previous_time, time) = match &self.previous_frame {
+            Some(p) => p,
+            None => return self.frequencies.clone(),
+        };
+        let interval = time.duration_since(*previous_time).as_secs_f32();
+        if interval <= 0.0 || previous.len() != self.frequencies.len() {
+            return self.frequencies.clone();
+        }
+        let t: f32 = (now.saturating_duration_since(*time).as_secs_f32() / interval).clamp(0.0, 1.0);
+
+        self.frequencies
+            .iter()
+            .zip(previous.iter())
+            .map(|(current, previous)| {
+                if current.len() != previous.len() {
+                    return current.clone();
+                }
+                current
+                    .iter()
+                    .zip(previous.iter())
+                    .map(|(c, p)| Frequency {
+                        volume: p.volume * (1.0 - t) + c.volume * t,
+                        ..c.clone()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// times of the two most recent frames, which `get_frequencies_interpolated()` blends between
+    #[cfg(feature = "std")]
+    pub fn last_frame_times(&self) -> Option<(Instant, Instant)> {
+        self.previous_frame.as_ref().map(|(_, previous, current)| (*previous, *current))
+    }
+
+    /// signed change of volume of every frequency since the previous frame, positive when energy rises
+    ///
+    /// bars are the same as returned by `get_frequencies()`, delta of the first frame is the frame itself
+    pub fn get_frequency_delta(&self) -> Vec<Vec<Frequency>> {
+        // interpolation only raises bars above zero, so both frames get finalized before they are subtracted
+        let previous = self.finalize(&self.previous_freq_buffer);
+        let mut delta = self.finalize(&self.freq_buffer);
+        for (current, previous) in delta.iter_mut().zip(previous.iter()) {
+            if previous.len() == current.len() {
+                for (freq, previous) in current.iter_mut().zip(previous.iter()) {
+                    freq.volume -= previous.volume;
+                }
+            }
+        }
+        delta
+    }
+
+    // FFT of every channel that is `ready`, in parallel with `parallel` feature and more than two channels
+    fn compute_spectra(&mut self, ready: &[bool]) -> Vec<Option<Spectrum>> {
+        let retain_complex = self.config.retain_complex;
+
+        #[cfg(feature = "parallel")]
+        {
+            let threads: usize = self.config.max_threads.unwrap_or_else(|| {
+                std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+            });
+            if self.raw_buffer.len() > 2 && threads > 1 {
+                let threads = threads.min(self.raw_buffer.len());
+                if self.pool.as_ref().map(|pool| pool.threads()) != Some(threads) {
+                    self.pool = Some(Pool::new(threads));
+                }
+                let jobs: Vec<(ProcessorConfig, Vec<f32>, bool)> = self.raw_buffer
+                    .iter()
+                    .enumerate()
+                    .filter(|(channel, _)| ready[*channel])
+                    .map(|(channel, raw_data)| (self.config.processor_for(channel).clone(), raw_data.clone(), retain_complex))
+                    .collect();
+                let mut spectra = self.pool.as_ref().unwrap().compute(jobs).into_iter();
+
+                return ready.iter().map(|ready| if *ready { spectra.next() } else { None }).collect();
+            }
+        }
+
+        self.raw_buffer
+            .iter()
+            .zip(ready.iter())
+            .enumerate()
+            .map(|(channel, (raw_data, ready))| {
+                if *ready {
+                    Some(compute_spectrum(self.config.processor_for(channel), raw_data, retain_complex))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// number of FFT plans created by worker threads of this stream
+    #[cfg(all(test, feature = "parallel"))]
+    pub(crate) fn pool_plans_created(&self) -> usize {
+        self.pool.as_ref().map(|pool| pool.plans_created()).unwrap_or(0)
+    }
+
+    // additional effects get applied here, that were skiped on `self.update()`
+    fn finalize(&self, data: &[Vec<Frequency>]) -> Vec<Vec<Frequency>> {
+        let mut buffer: Vec<Vec<Frequency>> = Vec::with_capacity(data.len());
+        for (channel, channel_data) in data.iter().enumerate() {
             let mut audio_data = Processor::from_frequencies(
-                self.config.clone().processor,
+                self.config.processor_for(channel).clone(),
                 channel_data.clone(),
             );
             audio_data.bound_frequencies();
             audio_data.interpolate();
-            
+
             buffer.push(audio_data.freq_buffer)
         }
         buffer
     }
-    /// calculates frequencies from raw data using FFT algorithm
+
+    // applies cached positions to `buffer`, cache gets rebuilt if it does not match anymore
+    fn lock_positions(&mut self, buffer: &mut [Vec<Frequency>]) {
+        if let Some((config, positions)) = &self.locked_positions {
+            let matches = *config == self.config.processor
+                && positions.len() == buffer.len()
+                && positions.iter().zip(buffer.iter()).all(|(p, b)| p.len() == b.len());
+            if matches {
+                for (freqs, positions) in buffer.iter_mut().zip(positions.iter()) {
+                    for (freq, position) in freqs.iter_mut().zip(positions.iter()) {
+                        freq.position = *position;
+                    }
+                }
+                return;
+            }
+        }
+        if buffer.iter().any(|b| !b.is_empty()) {
+            let positions = buffer.iter().map(|b| b.iter().map(|f| f.position).collect()).collect();
+            self.locked_positions = Some((self.config.processor.clone(), positions));
+        }
+    }
+    /// frequencies of all channels interleaved per bar, all channels of bar 0 first, then of bar 1 and so on
+    ///
+    /// assumes equal length of every channel, which is the case as long as every channel uses the same config,
+    /// bars that are not present in all channels get skipped
+    pub fn get_frequencies_interleaved(&mut self) -> Vec<Frequency> {
+        let channels = self.get_frequencies();
+        let bars: usize = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+
+        let mut buffer: Vec<Frequency> = Vec::with_capacity(bars * channels.len());
+        for bar in 0..bars {
+            for channel in channels.iter() {
+                buffer.push(channel[bar].clone());
+            }
+        }
+        buffer
+    }
+
+    /// full complex FFT of the last computed frame of every channel, including mirrored half
+    ///
+    /// `None` unless `config.retain_complex` is enabled and at least one frame was computed
+    pub fn get_complex_spectrum(&self) -> Option<Vec<Vec<Complex<f32>>>> {
+        if !self.config.retain_complex || self.complex_buffer.is_empty() {
+            return None;
+        }
+        Some(self.complex_buffer.clone())
+    }
+
+    /// processes mid (`(L + R) * 0.5`) and side (`(L - R) * 0.5`) signals of a stereo stream
+    ///
+    /// both signals are computed out of the raw audio data before FFT is applied,
+    /// so a centered signal only shows up in mid and an out of phase signal only in side
+    ///
+    /// returns `None` if stream does not have exactly two channels
+    pub fn get_mid_side_frequencies(&mut self) -> Option<(Vec<Frequency>, Vec<Frequency>)> {
+        if self.config.channel_count != 2 || self.raw_buffer.len() != 2 {
+            return None;
+        }
+        let left = &self.raw_buffer[0];
+        let right = &self.raw_buffer[1];
+
+        // only the newest samples are relevant
+        let len: usize = left.len().min(right.len()).min(self.config.fft_resolution);
+        let left = &left[left.len() - len..];
+        let right = &right[right.len() - len..];
+
+        let mid: Vec<f32> = left.iter().zip(right.iter()).map(|(l, r)| (l + r) * 0.5).collect();
+        let side: Vec<f32> = left.iter().zip(right.iter()).map(|(l, r)| (l - r) * 0.5).collect();
+
+        let mut mid = Processor::from_raw_data(self.config.processor.clone(), mid);
+        mid.compute_all();
+        let mut side = Processor::from_raw_data(self.config.processor.clone(), side);
+        side.compute_all();
+
+        Some((mid.freq_buffer, side.freq_buffer))
+    }
+
+    /// volumes of current frame of `channel` mapped to bytes, see `spectrum::frequencies_as_u8()`
+    ///
+    /// returns `None` if channel does not exist
+    pub fn frame_as_u8(&mut self, channel: usize, gamma: f32) -> Option<Vec<u8>> {
+        self.get_frequencies()
+            .get(channel)
+            .map(|freqs| frequencies_as_u8(freqs, gamma))
+    }
+
+    /// calculates frequencies from raw data using FFT algorithm, result can then be received with `get_frequencies()`
     /// 
     /// responsible for gravity so it should be called periodicly because I have not yet implemented delta time
     pub fn update(&mut self) {
         // processes on every channel
         let channels: usize = self.config.channel_count as usize;
-        for (channel, raw_data) in self.raw_buffer.iter_mut().enumerate() {
-            /* Prcesses data using spectralizer::Processor */
-            let fft_res: usize = self.config.fft_resolution;
-
-            if raw_data.len() > fft_res {
-                // clears unimportant buffer values that should already be processed
-                // and thus reduce latency
-                let diff = raw_data.len() - fft_res;
-                raw_data.drain(..diff);
-    
-                let mut audio_data = Processor::from_raw_data(
-                    self.config.clone().processor,
-                    raw_data[..].to_vec(),
-                );
-                audio_data.apodize();
-                audio_data.fft();
-                audio_data.normalize_frequency_volume();
-    
-                audio_data.raw_to_freq_buffer();
-                audio_data.normalize_frequency_position();
-                audio_data.distribute_frequency_position();
-    
-                let processed_buffer = audio_data.freq_buffer;
+        let mut computed: bool = false;
+
+        // skips FFT if last one is too recent, gravity still gets applied
+        #[cfg(feature = "std")]
+        if let (Some(rate), Some(last_fft)) = (self.config.max_fft_rate, self.last_fft) {
+            if last_fft.elapsed().as_secs_f32() < 1.0 / rate {
+                if let Some(gravity) = self.config.gravity {
+                    for (freqs, times) in self.freq_buffer.iter_mut().zip(self.gravity_time_buffer.iter_mut()) {
+                        for time in times.iter_mut() {
+                            *time += 1;
+                        }
+                        apply_gravity(freqs, times, gravity, self.config.gravity_floor);
+                    }
+                    self.refresh_frequencies();
+                }
+                return;
+            }
+        }
+        let spectra = match self.magnitudes.take() {
+            Some(magnitudes) => magnitudes
+                .into_iter()
+                .enumerate()
+                .map(|(channel, mags)| Some((process_magnitudes(self.config.processor_for(channel), mags), None)))
+                .collect(),
+            None => {
+                /* Prcesses data using spectralizer::Processor */
+                let fft_res: usize = self.config.fft_resolution;
+                let mut ready: Vec<bool> = vec![false; self.raw_buffer.len()];
+                for (raw_data, ready) in self.raw_buffer.iter_mut().zip(ready.iter_mut()) {
+                    if raw_data.len() > fft_res {
+                        // clears unimportant buffer values that should already be processed
+                        // and thus reduce latency
+                        let diff = raw_data.len() - fft_res;
+                        raw_data.drain(..diff);
+                        *ready = true;
+                    }
+                }
+                self.compute_spectra(&ready)
+            }
+        };
+
+        for (channel, spectrum) in spectra.into_iter().enumerate() {
+            if let Some((mut processed_buffer, complex)) = spectrum {
+                if let Some(complex) = complex {
+                    if self.complex_buffer.len() != channels {
+                        self.complex_buffer = vec![vec![]; channels];
+                    }
+                    self.complex_buffer[channel] = complex;
+                }
+                computed = true;
     
                 // freq_buffer allocation size check
                 if self.freq_buffer.len() != channels {
                     self.freq_buffer = vec![vec![Frequency::empty()]; channels];
                 }
+                // resampled instead of cleared, so that display does not go blank when number of frequencies changes
                 if self.freq_buffer[channel].len() != processed_buffer.len() {
-                    self.freq_buffer[channel] = vec![Frequency::empty(); processed_buffer.len()];
+                    self.freq_buffer[channel] = resample(&self.freq_buffer[channel], &processed_buffer);
                 }
 
+                // keeps previous frame for `get_frequency_delta()`
+                if self.previous_freq_buffer.len() != channels {
+                    self.previous_freq_buffer = vec![vec![]; channels];
+                }
+                self.previous_freq_buffer[channel] = self.freq_buffer[channel].clone();
+
                 // gravity time allocation size check
                 if self.gravity_time_buffer.len() != channels {
                     self.gravity_time_buffer = vec![vec![0]; channels];
                 }
                 if self.gravity_time_buffer[channel].len() != processed_buffer.len() {
-                    self.gravity_time_buffer[channel] = vec![0; processed_buffer.len()];
+                    let old = &self.gravity_time_buffer[channel];
+                    self.gravity_time_buffer[channel] = (0..processed_buffer.len())
+                        .map(|i| old.get(i * old.len() / processed_buffer.len()).cloned().unwrap_or(0))
+                        .collect();
+                }
+
+                if let Some(decay) = self.config.per_band_agc {
+                    // agc max allocation size check
+                    if self.agc_max_buffer.len() != channels {
+                        self.agc_max_buffer = vec![vec![0.0]; channels];
+                    }
+                    if self.agc_max_buffer[channel].len() != processed_buffer.len() {
+                        self.agc_max_buffer[channel] = vec![0.0; processed_buffer.len()];
+                    }
+
+                    for (freq, max) in processed_buffer.iter_mut().zip(self.agc_max_buffer[channel].iter_mut()) {
+                        *max *= decay;
+                        if freq.volume > *max {
+                            *max = freq.volume;
+                        }
+                        if *max > 0.0 {
+                            freq.volume /= *max;
+                        }
+                    }
+                }
+
+                if let Some(max_attack) = self.config.max_attack_per_frame {
+                    for (freq, last) in processed_buffer.iter_mut().zip(self.freq_buffer[channel].iter()) {
+                        freq.volume = freq.volume.min(last.volume + max_attack);
+                    }
                 }
 
                 match self.config.gravity {
@@ -130,15 +649,7 @@ impl Stream {
                         }
     
                         // apply gravity to buffer
-                        for (i, freq) in self.freq_buffer[channel].iter_mut().enumerate() {
-                            let gravity: f32 = gravity * 0.0025 * (self.gravity_time_buffer[channel][i] as f32);
-                            if freq.volume - gravity >= 0.0 {
-                                freq.volume -= gravity;
-                            } else {
-                                freq.volume = 0.0;
-                                self.gravity_time_buffer[channel][i] = 0;
-                            }
-                        }
+                        apply_gravity(&mut self.freq_buffer[channel], &mut self.gravity_time_buffer[channel], gravity, self.config.gravity_floor);
                     }
                     None => {
                         /* skips gravity */
@@ -147,5 +658,109 @@ impl Stream {
                 }
             }
         }
+
+        self.starved = !computed;
+        if self.starved {
+            match self.config.underrun_behavior {
+                UnderrunBehavior::HoldLast => (),
+                UnderrunBehavior::DecayToZero => {
+                    for freq in self.freq_buffer.iter_mut().flatten() {
+                        freq.volume *= 0.8;
+                    }
+                    self.refresh_frequencies();
+                }
+                UnderrunBehavior::Zero => {
+                    for freq in self.freq_buffer.iter_mut().flatten() {
+                        freq.volume = 0.0;
+                    }
+                    self.refresh_frequencies();
+                }
+            }
+        }
+
+        if computed {
+            self.frame_clock = self.sample_clock;
+            self.refresh_frequencies();
+
+            #[cfg(feature = "std")]
+            {
+                self.last_fft = Some(Instant::now());
+            }
+
+            if let Some(mut on_frame) = self.on_frame.take() {
+                on_frame(&self.get_frequencies());
+                self.on_frame = Some(on_frame);
+            }
+        }
+    }
+}
+
+// frequencies and optionally full complex FFT of single channel
+pub(crate) fn compute_spectrum(config: &ProcessorConfig, raw_data: &[f32], retain_complex: bool) -> Spectrum {
+    let mut audio_data = Processor::from_raw_data(config.clone(), raw_data.to_vec());
+    audio_data.apodize();
+    let mut complex = None;
+    if retain_complex {
+        complex = Some(audio_data.fft_complex());
+    } else {
+        audio_data.fft();
+    }
+    audio_data.suppress_frequencies();
+    audio_data.normalize_frequency_volume();
+
+    audio_data.raw_to_freq_buffer();
+    audio_data.normalize_frequency_position();
+    audio_data.distribute_frequency_position();
+
+    (audio_data.freq_buffer, complex)
+}
+
+// same as `compute_spectrum()` but starts after FFT, see `Stream::push_magnitudes()`
+fn process_magnitudes(config: &ProcessorConfig, mags: Vec<f32>) -> Vec<Frequency> {
+    let mut audio_data = Processor::from_raw_data(config.clone(), mags);
+    audio_data.suppress_frequencies();
+    audio_data.normalize_frequency_volume();
+
+    audio_data.raw_to_freq_buffer();
+    audio_data.normalize_frequency_position();
+    audio_data.distribute_frequency_position();
+
+    audio_data.freq_buffer
+}
+
+// linearly interpolates volumes of `old` to length of `new`, frequency and position are taken from `new`
+fn resample(old: &[Frequency], new: &[Frequency]) -> Vec<Frequency> {
+    new.iter()
+        .enumerate()
+        .map(|(i, freq)| {
+            let volume: f32 = if old.len() > 1 && new.len() > 1 {
+                let pos: f32 = i as f32 * (old.len() - 1) as f32 / (new.len() - 1) as f32;
+                let low: usize = (pos as usize).min(old.len() - 2);
+                let t: f32 = pos - low as f32;
+                old[low].volume * (1.0 - t) + old[low + 1].volume * t
+            } else {
+                old.first().map(|f| f.volume).unwrap_or(0.0)
+            };
+            Frequency {
+                volume,
+                ..freq.clone()
+            }
+        })
+        .collect()
+}
+
+// lets volume fall depending on how long each frequency has already been falling, but not below `floor`
+fn apply_gravity(freqs: &mut [Frequency], time_buffer: &mut [u32], gravity: f32, floor: f32) {
+    let floor: f32 = floor.max(0.0);
+    for (freq, time) in freqs.iter_mut().zip(time_buffer.iter_mut()) {
+        let gravity: f32 = gravity * 0.0025 * (*time as f32);
+        if freq.volume - gravity >= floor {
+            freq.volume -= gravity;
+        } else {
+            freq.volume = floor;
+            if floor == 0.0 {
+                *time = 0;
+            }
+        }
     }
 }
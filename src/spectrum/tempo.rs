@@ -0,0 +1,111 @@
+//! ## Example
+//! ```
+//! use audioviz::spectrum::tempo::TempoEstimator;
+//!
+//! let mut tempo = TempoEstimator::default();
+//!
+//! // times of detected onsets in seconds
+//! for i in 0..16 {
+//!     tempo.push_onset(i as f32 * 0.5);
+//! }
+//! let bpm = tempo.bpm().unwrap();
+//! assert!((bpm - 120.0).abs() < 1.0);
+//! ```
+
+use std::collections::VecDeque;
+
+// time resolution of onset envelope in seconds
+const ENVELOPE_RESOLUTION: f32 = 0.005;
+
+// onsets that are needed at least for an estimate
+const MIN_ONSETS: usize = 4;
+
+/// estimates tempo out of onset times using autocorrelation of the onset envelope
+///
+/// searches between `min_bpm` and `max_bpm` (default 60 to 180), shorter beat periods are preferred
+/// so that estimate does not fall to half tempo.
+///
+/// an estimate needs at least 4 onsets spanning two beats of `min_bpm` (2 seconds by default),
+/// stable values need about 6 to 8 seconds of observation
+#[derive(Clone, Debug)]
+pub struct TempoEstimator {
+    /// onsets older than this (in seconds) relative to newest onset are discarded
+    pub window: f32,
+    pub min_bpm: f32,
+    pub max_bpm: f32,
+    onsets: VecDeque<f32>,
+}
+impl Default for TempoEstimator {
+    fn default() -> Self {
+        Self::new(8.0)
+    }
+}
+impl TempoEstimator {
+    pub fn new(window: f32) -> Self {
+        Self {
+            window,
+            min_bpm: 60.0,
+            max_bpm: 180.0,
+            onsets: VecDeque::new(),
+        }
+    }
+
+    /// registers onset at `time` in seconds, times must be increasing
+    pub fn push_onset(&mut self, time: f32) {
+        self.onsets.push_back(time);
+        while let Some(first) = self.onsets.front() {
+            if time - first > self.window {
+                self.onsets.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.onsets.clear();
+    }
+
+    /// estimated tempo in beats per minute, `None` if not enough onsets were observed
+    pub fn bpm(&self) -> Option<f32> {
+        let (first, last) = (*self.onsets.front()?, *self.onsets.back()?);
+        if self.onsets.len() < MIN_ONSETS || last - first < 2.0 * 60.0 / self.min_bpm {
+            return None;
+        }
+
+        // onsets get split between two neighbouring samples, which keeps sub sample accuracy
+        let mut envelope: Vec<f32> = vec![0.0; ((last - first) / ENVELOPE_RESOLUTION) as usize + 2];
+        for onset in self.onsets.iter() {
+            let position = (onset - first) / ENVELOPE_RESOLUTION;
+            let i = position as usize;
+            let frac = position - i as f32;
+            envelope[i] += 1.0 - frac;
+            envelope[i + 1] += frac;
+        }
+
+        let min_lag = (60.0 / self.max_bpm / ENVELOPE_RESOLUTION).floor() as usize;
+        let max_lag = (60.0 / self.min_bpm / ENVELOPE_RESOLUTION).ceil() as usize;
+        let autocorrelation = |lag: usize| -> f32 {
+            envelope.iter().zip(envelope.iter().skip(lag)).map(|(a, b)| a * b).sum()
+        };
+
+        let correlations: Vec<f32> = (min_lag.max(2) - 1..=max_lag + 1).map(autocorrelation).collect();
+        let (peak, peak_value) = correlations
+            .iter()
+            .enumerate()
+            .skip(1)
+            .take(correlations.len() - 2)
+            .fold((0, 0.0), |max, (i, c)| if *c > max.1 { (i, *c) } else { max });
+        if peak == 0 || peak_value <= 0.0 {
+            return None;
+        }
+
+        // parabolic interpolation of peak
+        let (a, b, c) = (correlations[peak - 1], correlations[peak], correlations[peak + 1]);
+        let denominator = a - 2.0 * b + c;
+        let offset = if denominator.abs() > f32::EPSILON { (0.5 * (a - c) / denominator).clamp(-0.5, 0.5) } else { 0.0 };
+
+        let lag = (min_lag.max(2) - 1 + peak) as f32 + offset;
+        Some(60.0 / (lag * ENVELOPE_RESOLUTION))
+    }
+}
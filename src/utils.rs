@@ -1,5 +1,7 @@
 //! general utilities that help to process audio data
 
+use std::borrow::Cow;
+
 /// seperates 1 dimensional interleaved audio stream to 2D vector of audiodata of each channel
 pub fn seperate_channels(data: &[f32], channel_count: usize) -> Vec<Vec<f32>> {
     let mut buffer: Vec<Vec<f32>> = vec![vec![]; channel_count];
@@ -13,6 +15,350 @@ pub fn seperate_channels(data: &[f32], channel_count: usize) -> Vec<Vec<f32>> {
     buffer
 }
 
+/// averages all channels of every frame of interleaved `data` into a single channel
+///
+/// `channel_count` of 0 results in no data, 1 in an unchanged copy,
+/// an incomplete last frame gets averaged over the channels it contains
+pub fn downmix_to_mono(data: &[f32], channel_count: usize) -> Vec<f32> {
+    match channel_count {
+        0 => Vec::new(),
+        1 => data.to_vec(),
+        _ => data
+            .chunks(channel_count)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect(),
+    }
+}
+
+/// inverse of `seperate_channels()`, interleaves channels frame by frame to `L R L R ...`
+///
+/// if channels differ in length, output stops at the shortest one, see `planar_to_interleaved()` for a checked version
+pub fn interleave_channels(channels: &[Vec<f32>]) -> Vec<f32> {
+    let len: usize = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+    let planes: Vec<&[f32]> = channels.iter().map(|c| &c[..len]).collect();
+
+    // planes are truncated to equal length, so this never fails
+    planar_to_interleaved(&planes).unwrap_or_default()
+}
+
+/// same as `seperate_channels()`, splits interleaved data (`L R L R ...`) into one plane per channel
+pub fn interleaved_to_planar(data: &[f32], channel_count: usize) -> Vec<Vec<f32>> {
+    seperate_channels(data, channel_count)
+}
+
+/// interleaves planar data (every channel contiguous, as given by some decoders) to `L R L R ...`
+///
+/// returns `None` if planes differ in length
+pub fn planar_to_interleaved(planes: &[&[f32]]) -> Option<Vec<f32>> {
+    let len: usize = planes.first().map(|p| p.len()).unwrap_or(0);
+    if planes.iter().any(|p| p.len() != len) {
+        return None;
+    }
+
+    let mut buffer: Vec<f32> = Vec::with_capacity(len * planes.len());
+    for i in 0..len {
+        for plane in planes.iter() {
+            buffer.push(plane[i]);
+        }
+    }
+
+    Some(buffer)
+}
+
+/// channel order of a multichannel stream, order follows WAVE / SMPTE conventions
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ChannelLayout {
+    /// L, R
+    Stereo,
+
+    /// L, R, C, LFE, Ls, Rs
+    FivePointOne,
+
+    /// L, R, C, LFE, Lb, Rb, Ls, Rs
+    SevenPointOne,
+}
+impl ChannelLayout {
+    /// mono downmix coefficient of each channel
+    pub fn coefficients(&self) -> &'static [f32] {
+        const C: f32 = std::f32::consts::FRAC_1_SQRT_2;
+        const S: f32 = 0.5 * std::f32::consts::FRAC_1_SQRT_2;
+        match self {
+            ChannelLayout::Stereo => &[0.5, 0.5],
+            ChannelLayout::FivePointOne => &[0.5, 0.5, C, 0.0, S, S],
+            ChannelLayout::SevenPointOne => &[0.5, 0.5, C, 0.0, S, S, S, S],
+        }
+    }
+}
+
+/// folds down channels of a surround stream into a single channel
+///
+/// follows ITU-R BS.775 stereo downmix (center and surrounds at -3dB, LFE dropped),
+/// whose left and right outputs are then averaged:
+///
+/// | channel       | coefficient |
+/// |---------------|-------------|
+/// | L, R          | 0.5         |
+/// | C             | 0.707       |
+/// | LFE           | 0.0         |
+/// | each surround | 0.354       |
+///
+/// `channels` holds the data of each channel seperately, like returned by `seperate_channels()`,
+/// missing channels are treated as silent and output is as long as the shortest given channel
+pub fn downmix_surround(channels: &[Vec<f32>], layout: ChannelLayout) -> Vec<f32> {
+    let coefficients = layout.coefficients();
+    let len: usize = channels.iter().take(coefficients.len()).map(|c| c.len()).min().unwrap_or(0);
+
+    let mut buffer: Vec<f32> = vec![0.0; len];
+    for (channel, coefficient) in channels.iter().zip(coefficients.iter()) {
+        for (out, x) in buffer.iter_mut().zip(channel.iter()) {
+            *out += x * coefficient;
+        }
+    }
+
+    buffer
+}
+
+/// what `frame()` does with the last frame if it is shorter than `frame_size`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FrameTail {
+    /// incomplete frame is ignored
+    Drop,
+
+    /// first incomplete frame is filled up with zeros, so that every sample is contained in a frame
+    ZeroPad,
+}
+
+/// splits data into frames of `frame_size` that each start `hop_size` samples after the previous one
+///
+/// frames overlap when `hop_size` < `frame_size`,
+/// complete frames are borrowed from `data`, only a zero padded tail gets copied
+pub fn frame(data: &[f32], frame_size: usize, hop_size: usize, tail: FrameTail) -> Vec<Cow<'_, [f32]>> {
+    let hop_size = hop_size.max(1);
+    let mut frames: Vec<Cow<[f32]>> = Vec::new();
+    if frame_size == 0 {
+        return frames;
+    }
+
+    let mut start: usize = 0;
+    while start + frame_size <= data.len() {
+        frames.push(Cow::Borrowed(&data[start..start + frame_size]));
+        start += hop_size;
+    }
+
+    if tail == FrameTail::ZeroPad && start < data.len() {
+        let mut padded = data[start..].to_vec();
+        padded.resize(frame_size, 0.0);
+        frames.push(Cow::Owned(padded));
+    }
+
+    frames
+}
+
+/// buffers incoming chunks of any size and yields complete frames of `block_size`,
+/// for example of `fft_resolution`, like `frame()` but across multiple calls
+///
+/// each frame starts `hop_size` samples after the previous one,
+/// so frames overlap by `block_size - hop_size` samples when `hop_size` < `block_size`
+/// and samples in between frames are skipped when `hop_size` > `block_size`.
+/// samples of an incomplete frame are kept until enough data was pushed
+#[derive(Clone, Debug)]
+pub struct BlockAccumulator {
+    block_size: usize,
+    hop_size: usize,
+    buffer: Vec<f32>,
+    // samples that still have to be skipped because `hop_size` > buffered samples
+    skip: usize,
+}
+impl BlockAccumulator {
+    pub fn new(block_size: usize, hop_size: usize) -> Self {
+        Self {
+            block_size: block_size.max(1),
+            hop_size: hop_size.max(1),
+            buffer: Vec::with_capacity(block_size),
+            skip: 0,
+        }
+    }
+
+    /// appends `data` and returns all frames that are complete now, oldest first
+    pub fn push(&mut self, data: &[f32]) -> Vec<Vec<f32>> {
+        let skip = self.skip.min(data.len());
+        self.skip -= skip;
+        self.buffer.extend_from_slice(&data[skip..]);
+
+        let mut frames: Vec<Vec<f32>> = Vec::new();
+        let mut start: usize = 0;
+        while start + self.block_size <= self.buffer.len() {
+            frames.push(self.buffer[start..start + self.block_size].to_vec());
+            start += self.hop_size;
+        }
+        if start > self.buffer.len() {
+            self.skip = start - self.buffer.len();
+            self.buffer.clear();
+        } else {
+            self.buffer.drain(..start);
+        }
+
+        frames
+    }
+
+    /// number of buffered samples that are not yet part of a returned frame
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.skip = 0;
+    }
+}
+
+/// converts frequency in hz to its (fractional) midi note number
+///
+/// assumes A4 = 440hz = note 69, so C4 is 60
+pub fn freq_to_note(freq: f32) -> f32 {
+    69.0 + 12.0 * (freq / 440.0).log2()
+}
+
+/// stereo width in range (0..=1) from energy of side (`L - R`) relative to mid (`L + R`) and side
+///
+/// 0.0 for mono, 0.5 for uncorrelated channels and 1.0 for channels with opposite polarity,
+/// returns 0.0 for silence
+pub fn stereo_width(left: &[f32], right: &[f32]) -> f32 {
+    let (mut mid, mut side): (f32, f32) = (0.0, 0.0);
+    for (l, r) in left.iter().zip(right.iter()) {
+        mid += (l + r).powi(2);
+        side += (l - r).powi(2);
+    }
+    if mid + side <= 0.0 {
+        return 0.0;
+    }
+    side / (mid + side)
+}
+
+/// balance in range (-1..=1) from RMS of each channel, -1.0 is fully left and 1.0 fully right
+///
+/// returns 0.0 for silence
+pub fn balance(left: &[f32], right: &[f32]) -> f32 {
+    let (left, right) = (rms(left), rms(right));
+    if left + right <= 0.0 {
+        return 0.0;
+    }
+    (right - left) / (left + right)
+}
+
+/// momentary loudness of `data` in LUFS after simplified ITU-R BS.1770
+///
+/// signal is K-weighted (high shelf pre-filter and RLB highpass),
+/// loudness is then computed from mean square as `-0.691 + 10 * log10(mean_square)`.
+/// this is a estimate over the whole block, no gating of integrated loudness is done,
+/// so a full scale 1khz sine results in about -3 LUFS
+///
+/// returns `f32::NEG_INFINITY` for silence
+pub fn loudness_lufs(data: &[f32], sample_rate: f32) -> f32 {
+    if data.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let fs = sample_rate as f64;
+
+    // pre-filter, high shelf of about +4dB above 1.5khz
+    let k = (std::f64::consts::PI * 1681.974450955533 / fs).tan();
+    let q = 0.7071752369554196;
+    let vh = 10.0_f64.powf(3.999843853973347 / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = [
+        (vh + vb * k / q + k * k) / a0,
+        2.0 * (k * k - vh) / a0,
+        (vh - vb * k / q + k * k) / a0,
+        2.0 * (k * k - 1.0) / a0,
+        (1.0 - k / q + k * k) / a0,
+    ];
+
+    // RLB filter, highpass at about 38hz
+    let k = (std::f64::consts::PI * 38.13547087602444 / fs).tan();
+    let q = 0.5003270373238773;
+    let a0 = 1.0 + k / q + k * k;
+    let highpass = [1.0, -2.0, 1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0];
+
+    let mut shelf_state = [0.0_f64; 4];
+    let mut highpass_state = [0.0_f64; 4];
+    let mut sum: f64 = 0.0;
+    for x in data.iter() {
+        let y = biquad(*x as f64, &shelf, &mut shelf_state);
+        let y = biquad(y, &highpass, &mut highpass_state);
+        sum += y * y;
+    }
+    let mean_square = sum / data.len() as f64;
+    if mean_square <= 0.0 {
+        return f32::NEG_INFINITY;
+    }
+
+    (-0.691 + 10.0 * mean_square.log10()) as f32
+}
+
+// direct form 1 biquad with coefficients `[b0, b1, b2, a1, a2]` and state `[x1, x2, y1, y2]`
+fn biquad(x: f64, c: &[f64; 5], state: &mut [f64; 4]) -> f64 {
+    let [x1, x2, y1, y2] = *state;
+    let y = c[0] * x + c[1] * x1 + c[2] * x2 - c[3] * y1 - c[4] * y2;
+    *state = [x, x1, y, y1];
+    y
+}
+
+/// root mean square `sqrt(mean(x^2))` of `data`, 0.0 if `data` is empty
+pub fn rms(data: &[f32]) -> f32 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    (data.iter().map(|x| x * x).sum::<f32>() / data.len() as f32).sqrt()
+}
+
+/// highest absolute value of `data`, 0.0 if `data` is empty
+pub fn peak(data: &[f32]) -> f32 {
+    data.iter().fold(0.0, |max, x| max.max(x.abs()))
+}
+
+/// error of parsing note names
+#[derive(Clone, Debug, PartialEq)]
+pub enum NoteError {
+    /// string is not a note in format like `A4`, `C#3` or `Eb-1`
+    InvalidNote(String),
+
+    /// lower note is not below higher one
+    InvalidRange,
+}
+
+/// converts note name to its frequency in hz
+///
+/// name consists of letter (`A` to `G`), optional `#` or `b` and octave, like `A4`, `C#3` or `Eb-1`,
+/// tuning of A4 = 440hz is assumed
+pub fn note_to_freq(note: &str) -> Result<f32, NoteError> {
+    let invalid = || NoteError::InvalidNote(note.to_string());
+    let mut chars = note.trim().chars();
+
+    let mut semitone: i32 = match chars.next().map(|c| c.to_ascii_uppercase()) {
+        Some('C') => 0,
+        Some('D') => 2,
+        Some('E') => 4,
+        Some('F') => 5,
+        Some('G') => 7,
+        Some('A') => 9,
+        Some('B') => 11,
+        _ => return Err(invalid()),
+    };
+    let mut rest = chars.as_str();
+    if let Some(r) = rest.strip_prefix('#') {
+        semitone += 1;
+        rest = r;
+    } else if let Some(r) = rest.strip_prefix('b') {
+        semitone -= 1;
+        rest = r;
+    }
+    let octave: i32 = rest.parse().map_err(|_| invalid())?;
+
+    let midi = (octave + 1) * 12 + semitone;
+    Ok(440.0 * 2.0_f32.powf((midi - 69) as f32 / 12.0))
+}
+
 #[cfg(feature = "apodize")]
 pub fn apodize(data: &mut Vec<f32>) {
     let window = apodize::hanning_iter(data.len()).collect::<Vec<f64>>();